@@ -1,22 +1,81 @@
-use {smol_str::SmolStr, std::path::Path};
+use {
+    alpm::DepMod,
+    serde::{Deserialize, Serialize},
+    smol_str::SmolStr,
+    std::cmp::Ordering,
+};
 
-/// Filters out items from the package file list that are fully contained by the next item
-/// (e.g. `/usr/bin`) is removed if the next item is `/usr/bin/cat`
-pub fn deduped_files(list: &[alpm::File]) -> impl Iterator<Item = &alpm::File> {
-    list.array_windows()
-        .filter_map(|[a, b]| {
-            let retain = !path_contains_other_path(b.name().as_ref(), a.name().as_ref());
-            (retain).then_some(a)
-        })
-        .chain(list.last())
+/// Create a libalpm handle set up according to pacman.conf
+pub fn alpm_handle() -> anyhow::Result<alpm::Alpm> {
+    Ok(alpm_utils::alpm_with_conf(
+        &alpm_utils::config::Config::new()?,
+    )?)
 }
 
-fn path_contains_other_path(haystack: &Path, needle: &Path) -> bool {
-    haystack.parent() == Some(needle)
+/// Like [`alpm_handle`], but with the sync dbs read from the `.files` databases, which also
+/// have the file lists of the packages (like `pacman -F`)
+pub fn alpm_files_handle() -> anyhow::Result<alpm::Alpm> {
+    let conf = alpm_utils::config::Config::new()?;
+    let mut alpm = alpm::Alpm::new(&*conf.root_dir, &*conf.db_path)?;
+    // This has to happen before the sync dbs are registered
+    alpm.set_dbext(".files");
+    alpm_utils::configure_alpm(&mut alpm, &conf)?;
+    Ok(alpm)
+}
+
+/// Whether a `.pacnew` or `.pacsave` file holds the packaged or the previous version of a file
+#[derive(Clone, Copy, PartialEq)]
+pub enum PacFileKind {
+    New,
+    Save,
+}
+
+/// The file a `.pacnew`, `.pacsave` or `.pacsave.N` file is a version of
+pub fn pac_file_target(path: &str) -> Option<(&str, PacFileKind)> {
+    let (target, kind) = match path.strip_suffix(".pacnew") {
+        Some(target) => (target, PacFileKind::New),
+        None => {
+            let (target, n) = path.rsplit_once(".pacsave")?;
+            let numbered = n
+                .strip_prefix('.')
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+            if !n.is_empty() && !numbered {
+                return None;
+            }
+            (target, PacFileKind::Save)
+        }
+    };
+    (!target.is_empty() && !target.ends_with('/')).then_some((target, kind))
+}
+
+/// The package name and version in an archive file name, like `foo-1:2.0-1-x86_64.pkg.tar.zst`
+pub struct PkgFileName<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+}
+
+/// Parse the file name of a package archive. Signatures and partial downloads aren't archives.
+pub fn parse_pkg_filename(file: &str) -> Option<PkgFileName<'_>> {
+    let (stem, ext) = file.rsplit_once(".pkg.tar")?;
+    if !matches!(
+        ext,
+        "" | ".zst" | ".xz" | ".gz" | ".bz2" | ".lz4" | ".lrz" | ".lzo" | ".Z" | ".lz"
+    ) {
+        return None;
+    }
+    let mut parts = stem.rsplitn(4, '-');
+    let (arch, _rel, _ver, name) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if name.is_empty() {
+        return None;
+    }
+    Some(PkgFileName {
+        name,
+        version: &stem[name.len() + 1..stem.len() - arch.len() - 1],
+    })
 }
 
 /// A unique identifier for a package (db/name)
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct PkgId {
     pub db: SmolStr,
     pub name: SmolStr,
@@ -37,9 +96,6 @@ impl PkgId {
     pub fn is_remote(&self) -> bool {
         self.db != "local"
     }
-    pub fn matches_pkg(&self, pkg: &alpm::Package) -> bool {
-        pkg.db().is_some_and(|db| db.name() == self.db) && pkg.name() == self.name
-    }
 }
 
 impl std::fmt::Display for PkgId {
@@ -47,3 +103,282 @@ impl std::fmt::Display for PkgId {
         f.write_str(&[&self.db, "/", &self.name].concat())
     }
 }
+
+/// Something that can satisfy dependencies, by its name or by what it provides
+pub trait Satisfier {
+    fn name(&self) -> &str;
+    fn version(&self) -> &str;
+    /// The provisions, as name and optional version
+    fn provisions(&self) -> impl Iterator<Item = (&str, Option<&str>)>;
+}
+
+/// A dependency, like `sh` or `glibc>=2.40`
+#[derive(Clone, Copy)]
+pub struct DepSpec<'a> {
+    pub name: &'a str,
+    pub depmod: DepMod,
+    pub version: Option<&'a str>,
+}
+
+/// Whether `version` satisfies the constraint `depmod` `wanted` (e.g. `>=` `1.2`), with
+/// libalpm's version comparison rules
+pub fn version_satisfies(version: &str, depmod: DepMod, wanted: &str) -> bool {
+    let ord = alpm::vercmp(version, wanted);
+    match depmod {
+        DepMod::Any => true,
+        DepMod::Eq => ord == Ordering::Equal,
+        DepMod::Ge => ord != Ordering::Less,
+        DepMod::Le => ord != Ordering::Greater,
+        DepMod::Gt => ord == Ordering::Greater,
+        DepMod::Lt => ord == Ordering::Less,
+    }
+}
+
+impl DepSpec<'_> {
+    /// Whether something of `version` satisfies the version constraint.
+    ///
+    /// Like in libalpm, an unversioned provision only satisfies unversioned dependencies.
+    fn version_ok(&self, version: Option<&str>) -> bool {
+        match (self.depmod, self.version, version) {
+            (DepMod::Any, ..) | (_, None, _) => true,
+            (_, Some(_), None) => false,
+            (depmod, Some(wanted), Some(version)) => version_satisfies(version, depmod, wanted),
+        }
+    }
+    pub fn satisfied_by_name(&self, pkg: &impl Satisfier) -> bool {
+        pkg.name() == self.name && self.version_ok(Some(pkg.version()))
+    }
+    pub fn satisfied_by_provision(&self, pkg: &impl Satisfier) -> bool {
+        pkg.provisions()
+            .any(|(name, version)| name == self.name && self.version_ok(version))
+    }
+    pub fn satisfied_by(&self, pkg: &impl Satisfier) -> bool {
+        self.satisfied_by_name(pkg) || self.satisfied_by_provision(pkg)
+    }
+}
+
+impl std::fmt::Display for DepSpec<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name)?;
+        if let Some(ver) = self.version {
+            let op = match self.depmod {
+                DepMod::Any => "",
+                DepMod::Eq => "=",
+                DepMod::Ge => ">=",
+                DepMod::Le => "<=",
+                DepMod::Gt => ">",
+                DepMod::Lt => "<",
+            };
+            write!(f, "{op}{ver}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a dependency couldn't be resolved
+#[derive(Debug, PartialEq)]
+pub enum Unsatisfied {
+    /// No package has the name, or provides it
+    NotFound,
+    /// Packages have the name or provide it, but in the wrong version.
+    /// Each is described like `name 1.0-1` or `name (provides dep=1.0)`.
+    WrongVersion(Vec<String>),
+}
+
+impl std::fmt::Display for Unsatisfied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("no package has or provides this name"),
+            Self::WrongVersion(candidates) => {
+                write!(f, "wrong version: {}", candidates.join(", "))
+            }
+        }
+    }
+}
+
+/// Find the package satisfying `dep` among `pkgs`, which are in priority order (e.g. the sync
+/// dbs in pacman.conf order).
+///
+/// Like pacman, a package with the dependency's name is preferred over one providing it.
+pub fn resolve_dep<'a, P: Satisfier>(
+    dep: DepSpec,
+    pkgs: impl IntoIterator<Item = &'a P> + Clone,
+) -> Result<&'a P, Unsatisfied> {
+    if let Some(pkg) = pkgs
+        .clone()
+        .into_iter()
+        .find(|pkg| dep.satisfied_by_name(*pkg))
+    {
+        return Ok(pkg);
+    }
+    if let Some(pkg) = pkgs
+        .clone()
+        .into_iter()
+        .find(|pkg| dep.satisfied_by_provision(*pkg))
+    {
+        return Ok(pkg);
+    }
+    let mut candidates = Vec::new();
+    for pkg in pkgs {
+        if pkg.name() == dep.name {
+            candidates.push(format!("{} {}", pkg.name(), pkg.version()));
+        }
+        for (name, version) in pkg.provisions() {
+            if name == dep.name {
+                let prov = DepSpec {
+                    name,
+                    depmod: DepMod::Eq,
+                    version,
+                };
+                candidates.push(format!("{} (provides {prov})", pkg.name()));
+            }
+        }
+    }
+    if candidates.is_empty() {
+        Err(Unsatisfied::NotFound)
+    } else {
+        Err(Unsatisfied::WrongVersion(candidates))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestPkg {
+        name: &'static str,
+        version: &'static str,
+        provides: Vec<(&'static str, Option<&'static str>)>,
+    }
+
+    impl Satisfier for TestPkg {
+        fn name(&self) -> &str {
+            self.name
+        }
+        fn version(&self) -> &str {
+            self.version
+        }
+        fn provisions(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+            self.provides.iter().copied()
+        }
+    }
+
+    fn pkg(name: &'static str, version: &'static str) -> TestPkg {
+        TestPkg {
+            name,
+            version,
+            provides: Vec::new(),
+        }
+    }
+
+    fn dep(name: &'static str, depmod: DepMod, version: &'static str) -> DepSpec<'static> {
+        DepSpec {
+            name,
+            depmod,
+            version: Some(version),
+        }
+    }
+
+    fn any(name: &'static str) -> DepSpec<'static> {
+        DepSpec {
+            name,
+            depmod: DepMod::Any,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn version_constraints() {
+        assert!(version_satisfies("1.2-1", DepMod::Eq, "1.2-1"));
+        assert!(!version_satisfies("1.2-2", DepMod::Eq, "1.2-1"));
+        assert!(version_satisfies("1.2", DepMod::Ge, "1.2"));
+        assert!(version_satisfies("1.10", DepMod::Ge, "1.9"));
+        assert!(!version_satisfies("1.9", DepMod::Ge, "1.10"));
+        assert!(version_satisfies("1.9", DepMod::Lt, "1.10"));
+        assert!(!version_satisfies("1.10", DepMod::Lt, "1.10"));
+        assert!(version_satisfies("1.10", DepMod::Le, "1.10"));
+        assert!(version_satisfies("2.0", DepMod::Gt, "1.99"));
+        assert!(!version_satisfies("1.0", DepMod::Gt, "1.0"));
+        assert!(version_satisfies("0.1", DepMod::Any, "1.0"));
+    }
+
+    #[test]
+    fn version_constraints_follow_vercmp() {
+        // Without a pkgrel, the pkgrel isn't compared
+        assert!(version_satisfies("1.2-3", DepMod::Eq, "1.2"));
+        // The epoch wins over everything else
+        assert!(version_satisfies("1:0.1-1", DepMod::Gt, "9.9-1"));
+        // Pre-releases sort before the release
+        assert!(version_satisfies("1.0rc1", DepMod::Lt, "1.0"));
+    }
+
+    #[test]
+    fn unversioned_provision_only_satisfies_unversioned_dep() {
+        let pkg = TestPkg {
+            name: "bash",
+            version: "5.2-1",
+            provides: vec![("sh", None)],
+        };
+        assert!(any("sh").satisfied_by(&pkg));
+        assert!(!dep("sh", DepMod::Ge, "1").satisfied_by(&pkg));
+    }
+
+    #[test]
+    fn versioned_provision() {
+        let pkg = TestPkg {
+            name: "jre-openjdk",
+            version: "21.0.1-1",
+            provides: vec![("java-runtime", Some("21"))],
+        };
+        assert!(any("java-runtime").satisfied_by(&pkg));
+        assert!(dep("java-runtime", DepMod::Ge, "17").satisfied_by(&pkg));
+        assert!(dep("java-runtime", DepMod::Eq, "21").satisfied_by(&pkg));
+        assert!(!dep("java-runtime", DepMod::Lt, "21").satisfied_by(&pkg));
+        // The package's own version doesn't count for its provisions
+        assert!(!dep("java-runtime", DepMod::Eq, "21.0.1").satisfied_by(&pkg));
+    }
+
+    #[test]
+    fn resolve_prefers_name_over_provision() {
+        let provider = TestPkg {
+            name: "foo-git",
+            version: "2.0-1",
+            provides: vec![("foo", Some("2.0"))],
+        };
+        let pkgs = [provider, pkg("foo", "1.0-1")];
+        assert_eq!(resolve_dep(any("foo"), &pkgs).unwrap().name, "foo");
+        // ...unless the name doesn't satisfy the version
+        let dep = dep("foo", DepMod::Ge, "2");
+        assert_eq!(resolve_dep(dep, &pkgs).unwrap().name, "foo-git");
+    }
+
+    #[test]
+    fn resolve_in_priority_order() {
+        let pkgs = [pkg("foo", "1.0-1"), pkg("foo", "2.0-1")];
+        assert_eq!(resolve_dep(any("foo"), &pkgs).unwrap().version, "1.0-1");
+        let dep = dep("foo", DepMod::Ge, "2");
+        assert_eq!(resolve_dep(dep, &pkgs).unwrap().version, "2.0-1");
+    }
+
+    #[test]
+    fn resolve_explains_failure() {
+        let provider = TestPkg {
+            name: "foo-git",
+            version: "1.5-1",
+            provides: vec![("foo", Some("1.5"))],
+        };
+        let pkgs = [pkg("foo", "1.0-1"), provider, pkg("bar", "3.0-1")];
+        assert_eq!(
+            resolve_dep(any("baz"), &pkgs).unwrap_err(),
+            Unsatisfied::NotFound
+        );
+        assert_eq!(
+            resolve_dep(dep("foo", DepMod::Ge, "2"), &pkgs).unwrap_err(),
+            Unsatisfied::WrongVersion(vec![
+                "foo 1.0-1".into(),
+                "foo-git (provides foo=1.5)".into()
+            ])
+        );
+    }
+}
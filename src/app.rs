@@ -1,78 +1,48 @@
+pub mod config_files;
+pub mod dep_graph;
+pub mod file_index;
+pub mod file_tree;
+pub mod history;
+pub mod pac_state;
+pub mod pkg_cache;
+pub mod query;
 mod ui;
+pub mod verify;
 
 use {
-    crate::config::Config,
-    alpm::{Alpm, Package},
+    crate::{config::Config, job::Job},
     egui_colors::{Colorix, tokens::ThemeColor},
-    ouroboros::self_referencing,
+    pac_state::PacState,
+    std::sync::Arc,
     ui::UiState,
 };
 
 pub struct PacfrontApp {
-    pac: PacState,
+    pac: Arc<PacState>,
+    /// Loads the package databases in the background, at startup and on reload
+    pac_load: Option<Job<anyhow::Result<PacState>>>,
     ui: UiState,
     cfg: Config,
 }
 
-#[self_referencing]
-struct PacState {
-    alpm: Alpm,
-    #[borrows(alpm)]
-    db: &'this alpm::Db,
-    #[borrows(alpm)]
-    #[covariant]
-    sync: Vec<&'this alpm::Db>,
-    #[borrows(db)]
-    #[covariant]
-    local_pkg_list: Vec<&'this Package>,
-    #[borrows(db)]
-    #[covariant]
-    remote_pkg_list: Vec<&'this Package>,
-    #[borrows(db)]
-    #[covariant]
-    filt_local_pkg_list: Vec<&'this Package>,
-    #[borrows(db)]
-    #[covariant]
-    filt_remote_pkg_list: Vec<&'this Package>,
-}
-
-impl PacState {
-    fn gimme_new() -> anyhow::Result<Self> {
-        let alpm = alpm_utils::alpm_with_conf(&alpm_utils::config::Config::new()?)?;
-        let mut neu = PacStateBuilder {
-            alpm,
-            db_builder: |alpm| alpm.localdb(),
-            sync_builder: |alpm| alpm.syncdbs().into_iter().collect(),
-            local_pkg_list_builder: |db| db.pkgs().into_iter().collect(),
-            remote_pkg_list_builder: |_db| Vec::new(),
-            filt_local_pkg_list_builder: |_db| Vec::new(),
-            filt_remote_pkg_list_builder: |_db| Vec::new(),
-        }
-        .build();
-        neu.with_mut(|this| {
-            *this.filt_local_pkg_list = this.local_pkg_list.clone();
-            *this.remote_pkg_list = this.sync.iter_mut().flat_map(|db| db.pkgs()).collect();
-            *this.filt_remote_pkg_list = this.remote_pkg_list.clone();
-        });
-        Ok(neu)
-    }
-}
-
 impl PacfrontApp {
-    pub fn new() -> anyhow::Result<Self> {
-        Ok(Self {
-            pac: PacState::gimme_new()?,
+    pub fn new() -> Self {
+        Self {
+            pac: Arc::default(),
+            pac_load: Some(PacState::spawn_load()),
             ui: UiState::default(),
             cfg: Config::load_or_default(),
-        })
+        }
     }
     pub fn sync_from_config(&mut self, egui_ctx: &eframe::egui::Context) {
+        self.ui.load_cfg(&self.cfg);
         if let Some(color_theme) = &self.cfg.color_theme {
             self.ui.shared.colorix =
                 Some(Colorix::init(egui_ctx, color_theme.map(ThemeColor::Custom)))
         }
     }
     fn sync_to_config(&mut self) {
+        self.ui.save_cfg(&mut self.cfg);
         self.cfg.color_theme = self
             .ui
             .shared
@@ -84,8 +54,11 @@ impl PacfrontApp {
 
 impl eframe::App for PacfrontApp {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
+        ui::poll_pac_load(self);
         ui::top_panel_ui(self, ctx);
         ui::central_panel_ui(self, ctx);
+        ui::file_drop_ui(self, ctx);
+        ui::trans::modal_ui(self, ctx);
         ui::cmd::process_cmds(self, ctx);
     }
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
@@ -0,0 +1,321 @@
+use {
+    crate::alpm_util::{self, PacFileKind, pac_file_target},
+    smol_str::SmolStr,
+    std::{collections::HashMap, path::Path},
+};
+
+/// A file of a package that pacman doesn't overwrite on upgrades (usually configuration)
+pub struct BackupFile {
+    pub path: String,
+    pub state: BackupState,
+    /// There is a `.pacnew` file next to it
+    pub pacnew: bool,
+}
+
+pub enum BackupState {
+    /// The MD5 matches the one recorded when the package was installed
+    Unmodified,
+    Modified,
+    Missing,
+    Unreadable(String),
+}
+
+/// The backup files of a local package, compared with what was installed
+pub fn backup_files(name: &str) -> anyhow::Result<Vec<BackupFile>> {
+    let alpm = alpm_util::alpm_handle()?;
+    let root = Path::new(alpm.root());
+    let pkg = alpm.localdb().pkg(name)?;
+    Ok(pkg
+        .backup()
+        .iter()
+        .map(|backup| {
+            let path = root.join(backup.name());
+            let state = if !path.exists() {
+                BackupState::Missing
+            } else {
+                match alpm::compute_md5sum(path.to_string_lossy().as_bytes()) {
+                    Ok(sum) if sum == backup.hash() => BackupState::Unmodified,
+                    Ok(_) => BackupState::Modified,
+                    Err(e) => BackupState::Unreadable(e.to_string()),
+                }
+            };
+            let path = path.to_string_lossy().into_owned();
+            BackupFile {
+                pacnew: Path::new(&format!("{path}.pacnew")).exists(),
+                path,
+                state,
+            }
+        })
+        .collect())
+}
+
+/// A `.pacnew` or `.pacsave` file waiting to be dealt with
+pub struct PacFile {
+    pub path: String,
+    /// The file it's a version of
+    pub target: String,
+    pub kind: PacFileKind,
+    /// The package that has the target as a backup file
+    pub owner: Option<SmolStr>,
+    pub target_exists: bool,
+}
+
+/// Find the `.pacnew` and `.pacsave` files in `/etc`, like `pacdiff`
+pub fn find_pac_files() -> anyhow::Result<Vec<PacFile>> {
+    let alpm = alpm_util::alpm_handle()?;
+    let root = Path::new(alpm.root());
+    let mut owners = HashMap::new();
+    for pkg in alpm.localdb().pkgs() {
+        for backup in pkg.backup() {
+            owners.insert(
+                root.join(backup.name()).to_string_lossy().into_owned(),
+                SmolStr::from(pkg.name()),
+            );
+        }
+    }
+    let mut paths = Vec::new();
+    walk(&root.join("etc"), &mut paths);
+    let mut files: Vec<PacFile> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let (target, kind) = pac_file_target(&path)?;
+            let target = target.to_owned();
+            Some(PacFile {
+                owner: owners.get(&target).cloned(),
+                target_exists: Path::new(&target).exists(),
+                target,
+                kind,
+                path,
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Collect the paths of the files under `dir`, skipping what we can't read
+fn walk(dir: &Path, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(ft) = entry.file_type() else {
+            continue;
+        };
+        if ft.is_dir() {
+            walk(&entry.path(), out);
+        } else {
+            out.push(entry.path().to_string_lossy().into_owned());
+        }
+    }
+}
+
+/// A piece of a line diff. Lines keep their line endings.
+pub enum DiffChunk {
+    Same(Vec<String>),
+    Changed { old: Vec<String>, new: Vec<String> },
+}
+
+/// Beyond this many line pairs, the differing middle of two files is shown as one change
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Diff two texts line by line (longest common subsequence)
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffChunk> {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+    let mut chunks = Vec::new();
+    push_same(&mut chunks, &old[..prefix]);
+    if a.len() * b.len() > MAX_DIFF_CELLS {
+        push_changed(&mut chunks, a, b);
+    } else {
+        // lcs[i][j] is the length of the LCS of a[i..] and b[j..]
+        let w = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * w];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * w + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * w + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * w + j].max(lcs[i * w + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        let (mut old_start, mut new_start) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                push_changed(&mut chunks, &a[old_start..i], &b[new_start..j]);
+                push_same(&mut chunks, &a[i..=i]);
+                i += 1;
+                j += 1;
+                (old_start, new_start) = (i, j);
+            } else if j < b.len() && (i == a.len() || lcs[i * w + j + 1] >= lcs[(i + 1) * w + j]) {
+                j += 1;
+            } else {
+                i += 1;
+            }
+        }
+        push_changed(&mut chunks, &a[old_start..], &b[new_start..]);
+    }
+    push_same(&mut chunks, &old[old.len() - suffix..]);
+    chunks
+}
+
+fn push_same(chunks: &mut Vec<DiffChunk>, lines: &[&str]) {
+    if lines.is_empty() {
+        return;
+    }
+    let lines = lines.iter().map(|&line| line.to_owned());
+    match chunks.last_mut() {
+        Some(DiffChunk::Same(same)) => same.extend(lines),
+        _ => chunks.push(DiffChunk::Same(lines.collect())),
+    }
+}
+
+fn push_changed(chunks: &mut Vec<DiffChunk>, old: &[&str], new: &[&str]) {
+    if old.is_empty() && new.is_empty() {
+        return;
+    }
+    let owned = |lines: &[&str]| lines.iter().map(|&line| line.to_owned()).collect();
+    chunks.push(DiffChunk::Changed {
+        old: owned(old),
+        new: owned(new),
+    });
+}
+
+/// Put a file back together from a diff, taking the new side of the changes for which
+/// `take_new` returns `true`
+pub fn merge(chunks: &[DiffChunk], mut take_new: impl FnMut(usize) -> bool) -> String {
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let lines = match chunk {
+            DiffChunk::Same(lines) => lines,
+            DiffChunk::Changed { new, .. } if take_new(i) => new,
+            DiffChunk::Changed { old, .. } => old,
+        };
+        out.extend(lines.iter().map(String::as_str));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Merging with all old sides gives back `old`, and with all new sides gives `new`
+    fn assert_round_trip(old: &str, new: &str) {
+        let chunks = line_diff(old, new);
+        assert_eq!(
+            merge(&chunks, |_| false),
+            old,
+            "old side of {old:?} -> {new:?}"
+        );
+        assert_eq!(
+            merge(&chunks, |_| true),
+            new,
+            "new side of {old:?} -> {new:?}"
+        );
+    }
+
+    /// The diff as `=line` and `-old`/`+new` lines, with chunks separated by `|`
+    fn show(chunks: &[DiffChunk]) -> String {
+        let lines = |prefix: &str, lines: &[String]| {
+            lines
+                .iter()
+                .map(|line| format!("{prefix}{}", line.trim_end_matches('\n')))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        chunks
+            .iter()
+            .map(|chunk| match chunk {
+                DiffChunk::Same(same) => lines("=", same),
+                DiffChunk::Changed { old, new } => {
+                    format!("{} {}", lines("-", old), lines("+", new))
+                        .trim()
+                        .to_owned()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    #[test]
+    fn round_trips() {
+        let cases = [
+            ("a\nb\nc\n", "a\nb\nc\n"),
+            ("a\nb\nc\n", "a\nB\nc\n"),
+            ("a\nb\nc\n", "x\na\nc\nd\n"),
+            ("a\nb\na\nb\n", "b\na\nb\na\n"),
+            ("same\n", "same\nadded\n"),
+            ("removed\nsame\n", "same\n"),
+        ];
+        for (old, new) in cases {
+            assert_round_trip(old, new);
+            assert_round_trip(new, old);
+        }
+    }
+
+    #[test]
+    fn unchanged_file_is_one_chunk() {
+        let chunks = line_diff("a\nb\n", "a\nb\n");
+        assert_eq!(show(&chunks), "=a =b");
+    }
+
+    #[test]
+    fn changes_are_grouped() {
+        let chunks = line_diff("a\nb\nc\nd\n", "a\nB\nc\nD\ne\n");
+        assert_eq!(show(&chunks), "=a | -b +B | =c | -d +D +e");
+        // Taking only the second change
+        assert_eq!(merge(&chunks, |i| i == 3), "a\nb\nc\nD\ne\n");
+    }
+
+    #[test]
+    fn no_trailing_newline() {
+        assert_round_trip("a\nb", "a\nb\n");
+        assert_round_trip("a\nb", "a\nc");
+        assert_round_trip("a", "a");
+        // The last line differs only by its newline
+        let chunks = line_diff("a\nb", "a\nb\n");
+        assert_eq!(show(&chunks), "=a | -b +b");
+    }
+
+    #[test]
+    fn empty_files() {
+        assert!(line_diff("", "").is_empty());
+        assert_eq!(merge(&line_diff("", ""), |_| true), "");
+        assert_round_trip("", "a\nb\n");
+        assert_round_trip("a\nb\n", "");
+        assert_eq!(show(&line_diff("", "a\n")), "+a");
+    }
+
+    #[test]
+    fn large_changes_fall_back_to_one_chunk() {
+        let old: String = (0..3000).map(|i| format!("old {i}\n")).collect();
+        let new: String = (0..3000).map(|i| format!("new {i}\n")).collect();
+        let old = format!("head\n{old}tail\n");
+        let new = format!("head\n{new}tail\n");
+        let chunks = line_diff(&old, &new);
+        assert_eq!(chunks.len(), 3);
+        let DiffChunk::Changed {
+            old: removed,
+            new: added,
+        } = &chunks[1]
+        else {
+            panic!("the middle isn't a change");
+        };
+        assert_eq!((removed.len(), added.len()), (3000, 3000));
+        assert_round_trip(&old, &new);
+    }
+}
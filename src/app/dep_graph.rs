@@ -0,0 +1,262 @@
+use {
+    super::pac_state::PacState,
+    crate::alpm_util::PkgId,
+    std::{collections::HashMap, fmt::Write},
+};
+
+/// The dependency neighbourhood of a package, as a directed graph (dependent -> dependency)
+pub struct DepGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+pub struct GraphNode {
+    /// `None` for dependencies that can't be satisfied
+    pub id: Option<PkgId>,
+    pub label: String,
+    /// Distance from the root: positive for dependencies, negative for dependents
+    pub layer: i32,
+}
+
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub optional: bool,
+    /// The name depended on, if the dependency is satisfied through a provision
+    pub provision: Option<String>,
+}
+
+impl DepGraph {
+    /// The packages within `depth` dependency hops of `root`, and within `rdepth` hops of
+    /// packages depending on it
+    pub fn neighbourhood(
+        pac: &PacState,
+        root: &PkgId,
+        depth: u32,
+        rdepth: u32,
+        optional: bool,
+    ) -> Option<Self> {
+        let root_pkg = pac.find(root)?;
+        let mut graph = Self {
+            nodes: vec![GraphNode {
+                id: Some(root.clone()),
+                label: root_pkg.name.to_string(),
+                layer: 0,
+            }],
+            edges: Vec::new(),
+        };
+        let mut by_id = HashMap::from([(root.clone(), 0)]);
+        for reverse in [false, true] {
+            let (max, step) = if reverse { (rdepth, -1) } else { (depth, 1) };
+            let mut frontier = vec![0];
+            for dist in 1..=max {
+                let mut next = Vec::new();
+                for &node in &frontier {
+                    let Some(pkg) = graph.nodes[node].id.as_ref().and_then(|id| pac.find(id))
+                    else {
+                        continue;
+                    };
+                    for edge in pac.dep_edges(pkg, reverse, optional) {
+                        let other = match edge.pkg {
+                            Some(other) => *by_id.entry(other.id()).or_insert_with(|| {
+                                graph.nodes.push(GraphNode {
+                                    id: Some(other.id()),
+                                    label: other.name.to_string(),
+                                    layer: step * dist as i32,
+                                });
+                                next.push(graph.nodes.len() - 1);
+                                graph.nodes.len() - 1
+                            }),
+                            None => {
+                                graph.nodes.push(GraphNode {
+                                    id: None,
+                                    label: edge.dep.name.to_string(),
+                                    layer: step * dist as i32,
+                                });
+                                graph.nodes.len() - 1
+                            }
+                        };
+                        let (from, to) = if reverse {
+                            (other, node)
+                        } else {
+                            (node, other)
+                        };
+                        graph.edges.push(GraphEdge {
+                            from,
+                            to,
+                            optional: edge.optional,
+                            provision: edge.via_provides.then(|| edge.dep.name.to_string()),
+                        });
+                    }
+                }
+                frontier = next;
+            }
+        }
+        // Going both ways can find the same edge twice (e.g. in dependency cycles)
+        graph
+            .edges
+            .sort_by_key(|edge| (edge.from, edge.to, edge.optional));
+        graph
+            .edges
+            .dedup_by_key(|edge| (edge.from, edge.to, edge.optional));
+        Some(graph)
+    }
+    /// Export to Graphviz DOT
+    pub fn to_dot(&self) -> String {
+        let mut out =
+            String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut attrs = format!("label=\"{}\"", dot_escape(&node.label));
+            if node.layer == 0 {
+                attrs.push_str(", style=bold");
+            }
+            if node.id.is_none() {
+                attrs.push_str(", color=red");
+            }
+            writeln!(out, "    n{i} [{attrs}];").unwrap();
+        }
+        for edge in &self.edges {
+            let mut attrs = Vec::new();
+            if edge.optional {
+                attrs.push("style=dashed".to_owned());
+            }
+            if let Some(prov) = &edge.provision {
+                attrs.push(format!("label=\"{}\"", dot_escape(prov)));
+            }
+            let attrs = if attrs.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attrs.join(", "))
+            };
+            writeln!(out, "    n{} -> n{}{attrs};", edge.from, edge.to).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
+    /// Export to GraphML
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+            "  <key id=\"db\" for=\"node\" attr.name=\"db\" attr.type=\"string\"/>\n",
+            "  <key id=\"optional\" for=\"edge\" attr.name=\"optional\" attr.type=\"boolean\"/>\n",
+            "  <key id=\"provision\" for=\"edge\" attr.name=\"provision\" attr.type=\"string\"/>\n",
+            "  <graph id=\"dependencies\" edgedefault=\"directed\">\n",
+        ));
+        for (i, node) in self.nodes.iter().enumerate() {
+            writeln!(out, "    <node id=\"n{i}\">").unwrap();
+            writeln!(
+                out,
+                "      <data key=\"name\">{}</data>",
+                xml_escape(&node.label)
+            )
+            .unwrap();
+            if let Some(id) = &node.id {
+                writeln!(out, "      <data key=\"db\">{}</data>", xml_escape(&id.db)).unwrap();
+            }
+            out.push_str("    </node>\n");
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                out,
+                "    <edge id=\"e{i}\" source=\"n{}\" target=\"n{}\">",
+                edge.from, edge.to
+            )
+            .unwrap();
+            writeln!(out, "      <data key=\"optional\">{}</data>", edge.optional).unwrap();
+            if let Some(prov) = &edge.provision {
+                writeln!(
+                    out,
+                    "      <data key=\"provision\">{}</data>",
+                    xml_escape(prov)
+                )
+                .unwrap();
+            }
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A root with names that need escaping, an unsatisfied dependency, and an optional
+    /// dependency through a provision
+    fn graph() -> DepGraph {
+        let node = |name: &str, layer| GraphNode {
+            id: Some(PkgId::qualified("extra", name)),
+            label: name.into(),
+            layer,
+        };
+        DepGraph {
+            nodes: vec![
+                node(r#"lib"quoted""#, 0),
+                node("a<b>&c", 1),
+                node("gtk+", 1),
+                GraphNode {
+                    id: None,
+                    label: r"back\slash".into(),
+                    layer: 1,
+                },
+            ],
+            edges: vec![
+                GraphEdge {
+                    from: 0,
+                    to: 1,
+                    optional: false,
+                    provision: None,
+                },
+                GraphEdge {
+                    from: 0,
+                    to: 2,
+                    optional: true,
+                    provision: Some(r#"so<&"x""#.into()),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn dot_escaping() {
+        let dot = graph().to_dot();
+        assert!(dot.contains(r#"n0 [label="lib\"quoted\"", style=bold];"#));
+        assert!(dot.contains(r#"n1 [label="a<b>&c"];"#));
+        assert!(dot.contains(r#"n2 [label="gtk+"];"#));
+        assert!(dot.contains(r#"n3 [label="back\\slash", color=red];"#));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains(r#"n0 -> n2 [style=dashed, label="so<&\"x\""];"#));
+    }
+
+    #[test]
+    fn graphml_escaping() {
+        let xml = graph().to_graphml();
+        assert!(xml.contains(r#"<data key="name">lib&quot;quoted&quot;</data>"#));
+        assert!(xml.contains(r#"<data key="name">a&lt;b&gt;&amp;c</data>"#));
+        assert!(xml.contains(r#"<data key="name">gtk+</data>"#));
+        assert!(xml.contains(r#"<data key="name">back\slash</data>"#));
+        assert!(xml.contains(r#"<data key="provision">so&lt;&amp;&quot;x&quot;</data>"#));
+        // No raw `&` is left
+        for (i, _) in xml.match_indices('&') {
+            assert!(
+                ["&amp;", "&lt;", "&gt;", "&quot;"]
+                    .iter()
+                    .any(|entity| xml[i..].starts_with(entity))
+            );
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use {
+    crate::alpm_util::{self, PkgId},
+    regex::Regex,
+    smol_str::SmolStr,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Which packages own which paths, for looking up the owners of a file (`pacman -Qo`)
+pub struct FileIndex {
+    pkgs: Vec<PkgId>,
+    /// Paths as libalpm has them: relative to the root, and with a trailing `/` for directories
+    by_path: HashMap<String, Vec<u32>>,
+}
+
+impl FileIndex {
+    /// Index the files of every local package
+    pub fn load_local() -> anyhow::Result<Self> {
+        let alpm = alpm_util::alpm_handle()?;
+        let mut this = Self {
+            pkgs: Vec::new(),
+            by_path: HashMap::new(),
+        };
+        for pkg in alpm.localdb().pkgs() {
+            let idx = this.pkgs.len() as u32;
+            this.pkgs.push(PkgId::local(pkg.name()));
+            for file in pkg.files().files() {
+                this.by_path
+                    .entry(file.name().to_owned())
+                    .or_default()
+                    .push(idx);
+            }
+        }
+        Ok(this)
+    }
+    pub fn n_pkgs(&self) -> usize {
+        self.pkgs.len()
+    }
+    /// The packages owning `path`, which is in the form [`owner_query_path`] returns.
+    /// Directories are often owned by several packages.
+    pub fn owners(&self, path: &str) -> Vec<&PkgId> {
+        let dir = format!("{path}/");
+        [path, dir.as_str()]
+            .iter()
+            .filter_map(|path| self.by_path.get(*path))
+            .flatten()
+            .map(|&i| &self.pkgs[i as usize])
+            .collect()
+    }
+}
+
+/// Turn what the user asked about into a path the way `pacman -Qo` does: bare names are looked
+/// up in `PATH`, and symlinks are resolved in the parent directory (but not the file itself).
+///
+/// The result is relative to the root, without a trailing `/`.
+pub fn owner_query_path(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Empty path".into());
+    }
+    let path = if input.contains('/') {
+        PathBuf::from(input)
+    } else {
+        find_in_path(input).ok_or_else(|| format!("{input} not found in PATH"))?
+    };
+    let path = std::path::absolute(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let path = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => match parent.canonicalize() {
+            Ok(parent) => parent.join(name),
+            Err(_) => path,
+        },
+        _ => path,
+    };
+    let path = path.to_string_lossy();
+    Ok(path
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .to_owned())
+}
+
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| Path::new(path).is_file())
+}
+
+/// The files of a sync package matching a search
+pub struct FileMatch {
+    pub pkg: PkgId,
+    pub version: SmolStr,
+    pub files: Vec<String>,
+}
+
+/// A search for the sync packages that ship a file, like `pacman -F` (or `pacman -Fx` with
+/// `regex`).
+///
+/// A query with a `/` is matched against whole paths, otherwise against file names. Plain
+/// queries match the end of the path (so `bin/rg` finds `/usr/bin/rg`), and regexes see paths
+/// with their leading `/`.
+pub struct FileQuery {
+    /// Without leading `/`s, unless it's a regex
+    query: String,
+    by_path: bool,
+    re: Option<Regex>,
+}
+
+impl FileQuery {
+    pub fn new(query: &str, regex: bool) -> anyhow::Result<Self> {
+        let query = query.trim();
+        let query = if regex {
+            query
+        } else {
+            query.trim_start_matches('/')
+        };
+        Ok(Self {
+            query: query.to_owned(),
+            by_path: query.contains('/'),
+            re: regex.then(|| Regex::new(query)).transpose()?,
+        })
+    }
+    /// Whether the absolute `path` matches
+    fn matches(&self, path: &str) -> bool {
+        let path = path.trim_end_matches('/');
+        let subject = if self.by_path {
+            path
+        } else {
+            path.rsplit('/').next().unwrap_or(path)
+        };
+        match &self.re {
+            Some(re) => re.is_match(subject),
+            None if self.by_path => {
+                subject.ends_with(&self.query)
+                    && subject[..subject.len() - self.query.len()].ends_with('/')
+            }
+            None => subject == self.query,
+        }
+    }
+}
+
+/// The file lists of all sync packages, read once from the files databases for any number of
+/// searches
+pub struct SyncFileIndex {
+    pkgs: Vec<(PkgId, SmolStr)>,
+    /// The absolute paths of each package's files (directories with a trailing `/`), one per
+    /// line
+    files: Vec<String>,
+}
+
+impl SyncFileIndex {
+    pub fn load() -> anyhow::Result<Self> {
+        let alpm = alpm_util::alpm_files_handle()?;
+        if alpm.syncdbs().iter().all(|db| db.pkgs().is_empty()) {
+            anyhow::bail!("The files databases are missing. Synchronize them first (pacman -Fy).");
+        }
+        let mut this = Self {
+            pkgs: Vec::new(),
+            files: Vec::new(),
+        };
+        for db in alpm.syncdbs() {
+            for pkg in db.pkgs() {
+                this.pkgs.push((
+                    PkgId::qualified(db.name(), pkg.name()),
+                    pkg.version().as_str().into(),
+                ));
+                let mut files = String::new();
+                for file in pkg.files().files() {
+                    files.push('/');
+                    files.push_str(file.name());
+                    files.push('\n');
+                }
+                this.files.push(files);
+            }
+        }
+        Ok(this)
+    }
+    pub fn search(&self, query: &FileQuery) -> Vec<FileMatch> {
+        self.pkgs
+            .iter()
+            .zip(&self.files)
+            .filter_map(|((pkg, version), files)| {
+                let files: Vec<String> = files
+                    .lines()
+                    .filter(|file| query.matches(file))
+                    .map(str::to_owned)
+                    .collect();
+                (!files.is_empty()).then(|| FileMatch {
+                    pkg: pkg.clone(),
+                    version: version.clone(),
+                    files,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(query: &str, regex: bool, path: &str) -> bool {
+        FileQuery::new(query, regex).unwrap().matches(path)
+    }
+
+    #[test]
+    fn file_queries() {
+        assert!(matches("rg", false, "/usr/bin/rg"));
+        assert!(!matches("rg", false, "/usr/bin/rga"));
+        assert!(matches("bin/rg", false, "/usr/bin/rg"));
+        assert!(matches("/usr/bin/rg", false, "/usr/bin/rg"));
+        assert!(!matches("in/rg", false, "/usr/bin/rg"));
+        assert!(matches("usr/share/doc", false, "/usr/share/doc/"));
+        // Regexes keep their leading `/`
+        assert!(matches("^/usr/bin/r.$", true, "/usr/bin/rg"));
+        assert!(!matches("^/bin/rg", true, "/usr/bin/rg"));
+        assert!(matches("^r.$", true, "/usr/bin/rg"));
+        assert!(FileQuery::new("(", true).is_err());
+    }
+}
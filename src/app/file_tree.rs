@@ -0,0 +1,277 @@
+use {
+    crate::alpm_util::{self, PkgId},
+    flate2::read::GzDecoder,
+    std::{
+        collections::HashMap,
+        io::Read,
+        path::{Path, PathBuf},
+    },
+};
+
+/// The file list of a package as a directory tree
+pub struct FileTree {
+    /// The root directory itself, which isn't part of the package
+    pub root: FileNode,
+    /// Whether there is mtree metadata, and the files were checked on disk.
+    /// Only installed packages have this.
+    pub installed: bool,
+    pub n_files: usize,
+}
+
+pub struct FileNode {
+    pub name: String,
+    /// Relative to the root, without a trailing `/`
+    pub path: String,
+    pub is_dir: bool,
+    /// From the mtree of the package, if there is one
+    pub meta: Option<FileMeta>,
+    /// The file should be on disk, but isn't
+    pub missing: bool,
+    /// For directories, the total size of the files inside
+    pub size: u64,
+    /// Directories first, then sorted by name
+    pub children: Vec<FileNode>,
+}
+
+pub struct FileMeta {
+    pub kind: FileKind,
+    pub mode: u32,
+    pub size: u64,
+    pub uid: u32,
+    pub gid: u32,
+    /// Modification time, in seconds since the epoch
+    pub mtime: i64,
+    pub sha256: Option<String>,
+}
+
+pub enum FileKind {
+    File,
+    Dir,
+    Link(String),
+    Other(String),
+}
+
+impl FileKind {
+    pub fn label(&self) -> &str {
+        match self {
+            Self::File => "file",
+            Self::Dir => "directory",
+            Self::Link(_) => "symlink",
+            Self::Other(kind) => kind,
+        }
+    }
+}
+
+impl FileTree {
+    /// Load the file list of a package. Sync packages only have one if the files databases
+    /// have been downloaded (`pacman -Fy`).
+    pub fn load(id: &PkgId) -> anyhow::Result<Self> {
+        let alpm = if id.is_remote() {
+            alpm_util::alpm_files_handle()?
+        } else {
+            alpm_util::alpm_handle()?
+        };
+        let pkg = if id.is_remote() {
+            alpm.syncdbs()
+                .iter()
+                .find(|db| db.name() == id.db)
+                .ok_or_else(|| anyhow::anyhow!("No such database: {}", id.db))?
+                .pkg(id.name.as_str())
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "{id}: {e}. Maybe the files databases need to be synchronized (pacman -Fy)"
+                    )
+                })?
+        } else {
+            alpm.localdb().pkg(id.name.as_str())?
+        };
+        let (mut meta, root) = if id.is_remote() {
+            (HashMap::new(), None)
+        } else {
+            let meta = match read_mtree(&mtree_path(&alpm, pkg)) {
+                Ok(meta) => meta,
+                Err(e) => {
+                    eprintln!("Failed to read mtree of {id}: {e:#}");
+                    HashMap::new()
+                }
+            };
+            (meta, Some(Path::new(alpm.root())))
+        };
+        let mut this = Self {
+            root: FileNode::dir(String::new(), String::new()),
+            installed: root.is_some(),
+            n_files: 0,
+        };
+        for file in pkg.files().files() {
+            let name = file.name();
+            let path = name.trim_end_matches('/');
+            let node = this.root.get_or_insert(path);
+            node.is_dir = name.ends_with('/');
+            node.meta = meta.remove(path);
+            node.missing = root.is_some_and(|root| root.join(path).symlink_metadata().is_err());
+            if !node.is_dir {
+                this.n_files += 1;
+            }
+        }
+        this.root.finish();
+        Ok(this)
+    }
+}
+
+impl FileNode {
+    fn dir(name: String, path: String) -> Self {
+        Self {
+            name,
+            path,
+            is_dir: true,
+            meta: None,
+            missing: false,
+            size: 0,
+            children: Vec::new(),
+        }
+    }
+    /// The node at `path` under this one, creating it and its parents as needed
+    fn get_or_insert(&mut self, path: &str) -> &mut Self {
+        let mut node = self;
+        for component in path.split('/') {
+            // The file list is sorted, so the child is almost always the last one
+            let idx = match node.children.last() {
+                Some(last) if last.name == component => node.children.len() - 1,
+                _ => match node.children.iter().position(|c| c.name == component) {
+                    Some(idx) => idx,
+                    None => {
+                        let path = if node.path.is_empty() {
+                            component.to_owned()
+                        } else {
+                            format!("{}/{component}", node.path)
+                        };
+                        node.children.push(Self::dir(component.to_owned(), path));
+                        node.children.len() - 1
+                    }
+                },
+            };
+            node = &mut node.children[idx];
+        }
+        node
+    }
+    /// Sort the children and sum up the directory sizes
+    fn finish(&mut self) {
+        self.children
+            .sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        for child in &mut self.children {
+            child.finish();
+        }
+        self.size = if self.is_dir {
+            self.children.iter().map(|c| c.size).sum()
+        } else {
+            self.meta.as_ref().map_or(0, |meta| meta.size)
+        };
+    }
+}
+
+/// Where the mtree file of an installed package is
+pub fn mtree_path(alpm: &alpm::Alpm, pkg: &alpm::Pkg) -> PathBuf {
+    Path::new(alpm.dbpath())
+        .join("local")
+        .join(format!("{}-{}", pkg.name(), pkg.version()))
+        .join("mtree")
+}
+
+/// Read the metadata of the files of an installed package from its gzipped mtree file,
+/// keyed by path relative to the root
+pub fn read_mtree(path: &Path) -> anyhow::Result<HashMap<String, FileMeta>> {
+    let mut text = String::new();
+    GzDecoder::new(std::fs::File::open(path)?).read_to_string(&mut text)?;
+    Ok(parse_mtree(&text))
+}
+
+fn parse_mtree(text: &str) -> HashMap<String, FileMeta> {
+    let mut defaults: HashMap<&str, &str> = HashMap::new();
+    let mut entries = HashMap::new();
+    for line in text.lines() {
+        let mut words = line.split_ascii_whitespace();
+        let Some(first) = words.next() else {
+            continue;
+        };
+        match first {
+            _ if first.starts_with('#') => {}
+            "/set" => defaults.extend(words.filter_map(|word| word.split_once('='))),
+            "/unset" => {
+                for word in words {
+                    defaults.remove(word);
+                }
+            }
+            _ => {
+                let Some(path) = first.strip_prefix("./") else {
+                    continue;
+                };
+                let mut keywords = defaults.clone();
+                keywords.extend(words.filter_map(|word| word.split_once('=')));
+                let kind = match keywords.get("type").copied().unwrap_or("file") {
+                    "file" => FileKind::File,
+                    "dir" => FileKind::Dir,
+                    "link" => FileKind::Link(unescape(keywords.get("link").unwrap_or(&""))),
+                    other => FileKind::Other(other.to_owned()),
+                };
+                let num = |key| keywords.get(key).and_then(|v| v.parse().ok()).unwrap_or(0);
+                let meta = FileMeta {
+                    kind,
+                    mode: keywords
+                        .get("mode")
+                        .and_then(|mode| u32::from_str_radix(mode, 8).ok())
+                        .unwrap_or(0),
+                    size: num("size"),
+                    uid: num("uid") as u32,
+                    gid: num("gid") as u32,
+                    // Like `1700000000.0`
+                    mtime: keywords
+                        .get("time")
+                        .and_then(|time| time.split('.').next()?.parse().ok())
+                        .unwrap_or(0),
+                    sha256: keywords
+                        .get("sha256digest")
+                        .map(|&digest| digest.to_owned()),
+                };
+                entries.insert(unescape(path), meta);
+            }
+        }
+    }
+    entries
+}
+
+/// Undo the octal escapes (`\040`) mtree uses for special characters in paths
+fn unescape(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(digits) = s.get(i + 1..i + 4)
+            && let Ok(byte) = u8::from_str_radix(digits, 8)
+        {
+            out.push(byte);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `ls -l` style permissions, like `rwxr-xr-x`
+pub fn mode_string(mode: u32) -> String {
+    let mut out = String::with_capacity(9);
+    for (shift, special, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = mode >> shift;
+        out.push(if bits & 4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 2 != 0 { 'w' } else { '-' });
+        out.push(match (bits & 1 != 0, mode & special != 0) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
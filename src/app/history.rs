@@ -0,0 +1,612 @@
+use {
+    super::{
+        pac_state::PacState,
+        pkg_cache::{self, CachedPkg},
+    },
+    crate::{
+        alpm_util,
+        trans::{PkgOp, PkgOpKind, Reason, TransOp},
+    },
+    chrono::{DateTime, Local, NaiveDateTime, TimeZone},
+    smol_str::SmolStr,
+    std::collections::HashSet,
+};
+
+/// The package transactions recorded in pacman.log, oldest first
+pub struct History {
+    pub transactions: Vec<Transaction>,
+    /// Install reason changes with `pacman -D`, which aren't transactions
+    pub reason_changes: Vec<ReasonChange>,
+}
+
+pub struct Transaction {
+    /// When it started, as a unix timestamp
+    pub time: i64,
+    /// The command that ran it, like `pacman -Syu`
+    pub command: Option<String>,
+    pub outcome: Outcome,
+    pub events: Vec<PkgEvent>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Completed,
+    Failed,
+    Interrupted,
+    /// Still running, or cut off (older logs don't record transaction boundaries)
+    Unknown,
+}
+
+pub struct PkgEvent {
+    pub time: i64,
+    pub op: PkgOp,
+}
+
+pub struct ReasonChange {
+    pub time: i64,
+    pub reason: Reason,
+    pub names: Vec<SmolStr>,
+}
+
+impl History {
+    /// Read the log file configured in pacman.conf
+    pub fn load() -> anyhow::Result<Self> {
+        let conf = alpm_utils::config::Config::new()?;
+        let text = std::fs::read(&conf.log_file)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", conf.log_file))?;
+        Ok(Self::parse(&String::from_utf8_lossy(&text)))
+    }
+    pub fn parse(log: &str) -> Self {
+        let mut transactions: Vec<Transaction> = Vec::new();
+        let mut reason_changes = Vec::new();
+        // The last command pacman logged, which the next transaction belongs to
+        let mut command = None;
+        let mut open = false;
+        for line in log.lines() {
+            let Some((time, source, msg)) = parse_line(line) else {
+                continue;
+            };
+            match (source, msg) {
+                ("PACMAN", msg) => {
+                    if let Some(cmd) = msg
+                        .strip_prefix("Running '")
+                        .and_then(|cmd| cmd.strip_suffix('\''))
+                    {
+                        let parsed = parse_command(cmd);
+                        if parsed.op == Some('D')
+                            && let Some(reason) = parsed.reason
+                        {
+                            reason_changes.push(ReasonChange {
+                                time,
+                                reason,
+                                names: parsed.targets.into_iter().map(SmolStr::from).collect(),
+                            });
+                            // Not a transaction, so it has no events to own the command
+                            command = None;
+                        } else {
+                            command = Some(cmd.to_owned());
+                        }
+                        open = false;
+                    }
+                }
+                ("ALPM", "transaction started") => {
+                    transactions.push(Transaction {
+                        time,
+                        command: command.take(),
+                        outcome: Outcome::Unknown,
+                        events: Vec::new(),
+                    });
+                    open = true;
+                }
+                (
+                    "ALPM",
+                    "transaction completed" | "transaction failed" | "transaction interrupted",
+                ) => {
+                    if open && let Some(trans) = transactions.last_mut() {
+                        trans.outcome = match msg {
+                            "transaction completed" => Outcome::Completed,
+                            "transaction failed" => Outcome::Failed,
+                            _ => Outcome::Interrupted,
+                        };
+                    }
+                    open = false;
+                }
+                ("ALPM", msg) => {
+                    let Some(op) = parse_op(msg) else {
+                        continue;
+                    };
+                    if !open {
+                        // Without transaction boundaries, group the events by command
+                        transactions.push(Transaction {
+                            time,
+                            command: command.take(),
+                            outcome: Outcome::Unknown,
+                            events: Vec::new(),
+                        });
+                        open = true;
+                    }
+                    if let Some(trans) = transactions.last_mut() {
+                        trans.events.push(PkgEvent { time, op });
+                    }
+                }
+                _ => {}
+            }
+        }
+        transactions.retain(|trans| !trans.events.is_empty());
+        Self {
+            transactions,
+            reason_changes,
+        }
+    }
+    /// Everything that happened to a package, oldest first
+    pub fn pkg_events<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a PkgEvent> {
+        self.transactions
+            .iter()
+            .flat_map(|trans| &trans.events)
+            .filter(move |ev| ev.op.name == name)
+    }
+}
+
+/// What it takes to undo a transaction
+pub struct Rollback {
+    pub ops: Vec<TransOp>,
+    /// The install reasons to restore for the packages that get reinstalled
+    pub set_reasons: Vec<(SmolStr, Reason)>,
+    /// Parts that can't be undone, and other things to know before going ahead
+    pub notes: Vec<String>,
+}
+
+impl History {
+    /// Work out how to get the packages of the transaction `idx` back to the versions they had
+    /// before it: remove what it installed, and install the old versions of what it upgraded,
+    /// downgraded or removed (from `cache`, or a sync db that still has them).
+    ///
+    /// Removed packages get back the install reason they had, as far as the log tells.
+    /// Upgraded and downgraded packages keep theirs anyway.
+    pub fn rollback(&self, idx: usize, pac: &PacState, cache: &[CachedPkg]) -> Rollback {
+        let trans = &self.transactions[idx];
+        let mut rollback = Rollback {
+            ops: Vec::new(),
+            set_reasons: Vec::new(),
+            notes: Vec::new(),
+        };
+        let mut seen = HashSet::new();
+        for ev in &trans.events {
+            let op = &ev.op;
+            // The first event of a package has the version from before the transaction
+            if !seen.insert(&op.name) {
+                continue;
+            }
+            let installed = pac.find_local(&op.name);
+            if let (Some(pkg), Some(new)) = (installed, &op.new_ver)
+                && pkg.version != *new
+            {
+                rollback.notes.push(format!(
+                    "{} has changed since: {} is installed now",
+                    op.name, pkg.version
+                ));
+            }
+            match op.kind {
+                PkgOpKind::Reinstall => {}
+                PkgOpKind::Install => {
+                    if installed.is_some() {
+                        rollback.ops.push(TransOp::Remove(op.name.clone()));
+                    }
+                }
+                PkgOpKind::Upgrade | PkgOpKind::Downgrade | PkgOpKind::Remove => {
+                    let Some(old) = &op.old_ver else {
+                        continue;
+                    };
+                    if installed.is_some_and(|pkg| pkg.version == *old) {
+                        continue;
+                    }
+                    if op.kind == PkgOpKind::Remove {
+                        match self.reason_before(idx, &op.name, pac) {
+                            Some(Reason::Explicit) => {}
+                            Some(Reason::Depend) => {
+                                rollback.set_reasons.push((op.name.clone(), Reason::Depend));
+                            }
+                            None => rollback.notes.push(format!(
+                                "The log doesn't tell how {} was installed, \
+                                 so it will be marked as explicitly installed",
+                                op.name
+                            )),
+                        }
+                    }
+                    if let Some(cached) = pkg_cache::find(cache, &op.name, old) {
+                        rollback.ops.push(TransOp::InstallFile(
+                            cached.path.to_string_lossy().into_owned(),
+                        ));
+                    } else if let Some(remote) = pac
+                        .remote_pkg_list
+                        .iter()
+                        .find(|pkg| pkg.name == op.name && pkg.version == *old)
+                    {
+                        rollback.ops.push(TransOp::Install(remote.id()));
+                    } else {
+                        rollback.notes.push(format!(
+                            "{} {old} is neither in the package cache nor in a sync db",
+                            op.name
+                        ));
+                    }
+                }
+            }
+        }
+        rollback
+    }
+    /// The install reason the package `name` had right before the transaction `idx`, going by
+    /// the commands that installed it or changed its reason
+    fn reason_before(&self, idx: usize, name: &str, pac: &PacState) -> Option<Reason> {
+        let before = self.transactions[idx].time;
+        // The most recent (time, reason) so far
+        let mut latest: Option<(i64, Option<Reason>)> = None;
+        for trans in &self.transactions[..idx] {
+            let Some(ev) = trans.events.iter().find(|ev| ev.op.name == name) else {
+                continue;
+            };
+            let cmd = trans.command.as_deref().map(parse_command);
+            let reason = match (ev.op.kind, &cmd) {
+                // Installed by some other libalpm frontend
+                (PkgOpKind::Install, None) => None,
+                (PkgOpKind::Install, Some(cmd)) => Some(cmd.install_reason(name, pac)),
+                // Reinstalling or upgrading keeps the reason, unless asked otherwise
+                (_, Some(cmd)) if cmd.reason.is_some() && cmd.targets_pkg(name, pac) => cmd.reason,
+                _ => continue,
+            };
+            latest = Some((ev.time, reason));
+        }
+        for change in &self.reason_changes {
+            if change.time < before
+                && latest.is_none_or(|(time, _)| change.time >= time)
+                && change.names.iter().any(|n| n == name)
+            {
+                latest = Some((change.time, Some(change.reason)));
+            }
+        }
+        latest?.1
+    }
+}
+
+/// The parts of a logged pacman command line that matter for install reasons
+struct Command<'a> {
+    /// The operation, like `S` for `pacman -S`
+    op: Option<char>,
+    /// `--asdeps` or `--asexplicit`
+    reason: Option<Reason>,
+    /// Package names, or other targets like groups
+    targets: Vec<&'a str>,
+}
+
+impl Command<'_> {
+    /// Whether the command names the package `name`, or a group of it
+    fn targets_pkg(&self, name: &str, pac: &PacState) -> bool {
+        let groups = pac.find_remote(name).map_or(&[][..], |pkg| &pkg.groups[..]);
+        self.targets
+            .iter()
+            .any(|target| *target == name || groups.iter().any(|group| group == target))
+    }
+    /// The install reason this command gave to the package `name` when it installed it:
+    /// explicit for targets, and dependency for what it pulled in
+    fn install_reason(&self, name: &str, pac: &PacState) -> Reason {
+        match self.reason {
+            Some(reason) => reason,
+            None if self.targets_pkg(name, pac) => Reason::Explicit,
+            None => Reason::Depend,
+        }
+    }
+}
+
+/// Options that take a value as the next argument
+const VALUE_OPTIONS: &[&str] = &[
+    "--arch",
+    "--assume-installed",
+    "--cachedir",
+    "--color",
+    "--config",
+    "--dbpath",
+    "--gpgdir",
+    "--hookdir",
+    "--ignore",
+    "--ignoregroup",
+    "--logfile",
+    "--overwrite",
+    "--print-format",
+    "--root",
+    "--sysroot",
+];
+
+/// Parse a command like `pacman -S --asdeps extra/foo ./bar-1.0-1-x86_64.pkg.tar.zst`
+fn parse_command(cmd: &str) -> Command<'_> {
+    let mut parsed = Command {
+        op: None,
+        reason: None,
+        targets: Vec::new(),
+    };
+    // Skip the program name
+    let mut args = cmd.split_whitespace().skip(1);
+    let mut only_targets = false;
+    while let Some(arg) = args.next() {
+        if only_targets || !arg.starts_with('-') {
+            // Archives (`-U`), and packages qualified with their db (`-S`)
+            let file = arg.rsplit('/').next().unwrap_or(arg);
+            let name = alpm_util::parse_pkg_filename(file).map_or(file, |file| file.name);
+            parsed.targets.push(name);
+        } else if arg == "--" {
+            only_targets = true;
+        } else if arg == "--asdeps" {
+            parsed.reason = Some(Reason::Depend);
+        } else if arg == "--asexplicit" {
+            parsed.reason = Some(Reason::Explicit);
+        } else if arg.starts_with("--") {
+            if VALUE_OPTIONS.contains(&arg) {
+                args.next();
+            }
+        } else {
+            for (i, c) in arg.char_indices().skip(1) {
+                if c.is_ascii_uppercase() && parsed.op.is_none() {
+                    parsed.op = Some(c);
+                }
+                // `-r <root>` and `-b <dbpath>`, unless the value is attached
+                if matches!(c, 'r' | 'b') {
+                    if i + 1 == arg.len() {
+                        args.next();
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    parsed
+}
+
+/// Split `[2024-01-15T10:24:02+0100] [ALPM] installed foo (1.0-1)` into its parts
+fn parse_line(line: &str) -> Option<(i64, &str, &str)> {
+    let (time, rest) = line.strip_prefix('[')?.split_once("] ")?;
+    let (source, msg) = rest.strip_prefix('[')?.split_once("] ")?;
+    Some((parse_time(time)?, source, msg))
+}
+
+fn parse_time(time: &str) -> Option<i64> {
+    if let Ok(time) = DateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S%z") {
+        return Some(time.timestamp());
+    }
+    // Older pacman versions logged local time, without seconds
+    let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").ok()?;
+    Some(Local.from_local_datetime(&time).earliest()?.timestamp())
+}
+
+/// Parse `upgraded foo (1.0-1 -> 1.1-1)` and the like
+fn parse_op(msg: &str) -> Option<PkgOp> {
+    let (verb, rest) = msg.split_once(' ')?;
+    let kind = match verb {
+        "installed" => PkgOpKind::Install,
+        "upgraded" => PkgOpKind::Upgrade,
+        "reinstalled" => PkgOpKind::Reinstall,
+        "downgraded" => PkgOpKind::Downgrade,
+        "removed" => PkgOpKind::Remove,
+        _ => return None,
+    };
+    let (name, versions) = rest.split_once(" (")?;
+    let versions = versions.strip_suffix(')')?;
+    let (old_ver, new_ver) = match versions.split_once(" -> ") {
+        Some((old, new)) => (Some(old.into()), Some(new.into())),
+        None if kind == PkgOpKind::Remove => (Some(versions.into()), None),
+        None if kind == PkgOpKind::Reinstall => (Some(versions.into()), Some(versions.into())),
+        None => (None, Some(versions.into())),
+    };
+    Some(PkgOp {
+        kind,
+        name: SmolStr::from(name),
+        old_ver,
+        new_ver,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each event as `kind name old new`, with `-` for missing versions
+    fn show_events(trans: &Transaction) -> Vec<String> {
+        trans
+            .events
+            .iter()
+            .map(|ev| {
+                let op = &ev.op;
+                format!(
+                    "{} {} {} {}",
+                    op.kind.label(),
+                    op.name,
+                    op.old_ver.as_deref().unwrap_or("-"),
+                    op.new_ver.as_deref().unwrap_or("-")
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn iso_timestamps() {
+        let history = History::parse(
+            "[2024-01-15T10:24:02+0100] [PACMAN] Running 'pacman -Syu'\n\
+             [2024-01-15T10:24:03+0100] [PACMAN] synchronizing package lists\n\
+             [2024-01-15T10:24:05+0100] [ALPM] transaction started\n\
+             [2024-01-15T10:24:06+0100] [ALPM] upgraded foo (1.0-1 -> 1.1-1)\n\
+             [2024-01-15T10:24:06+0100] [ALPM-SCRIPTLET] some output\n\
+             [2024-01-15T10:24:07+0100] [ALPM] downgraded bar (2.0-1 -> 1.9-1)\n\
+             [2024-01-15T10:24:08+0100] [ALPM] transaction completed\n",
+        );
+        assert_eq!(history.transactions.len(), 1);
+        let trans = &history.transactions[0];
+        assert_eq!(trans.time, 1705310645);
+        assert_eq!(trans.command.as_deref(), Some("pacman -Syu"));
+        assert!(trans.outcome == Outcome::Completed);
+        assert_eq!(
+            show_events(trans),
+            ["Upgrade foo 1.0-1 1.1-1", "Downgrade bar 2.0-1 1.9-1"]
+        );
+        assert_eq!(trans.events[1].time, 1705310647);
+    }
+
+    #[test]
+    fn old_timestamps_without_transaction_boundaries() {
+        let history = History::parse(
+            "[2014-03-02 18:10] [PACMAN] Running 'pacman -S foo'\n\
+             [2014-03-02 18:10] [ALPM] installed foo (1.0-1)\n\
+             [2014-03-02 18:11] [ALPM] installed libfoo (0.5-2)\n\
+             [2014-03-02 18:20] [PACMAN] Running 'pacman -R foo'\n\
+             [2014-03-02 18:21] [ALPM] removed foo (1.0-1)\n",
+        );
+        let local = |time: &str| {
+            let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap();
+            Local
+                .from_local_datetime(&time)
+                .earliest()
+                .unwrap()
+                .timestamp()
+        };
+        // Grouped by the command that ran them
+        assert_eq!(history.transactions.len(), 2);
+        let (install, remove) = (&history.transactions[0], &history.transactions[1]);
+        assert_eq!(install.time, local("2014-03-02 18:10"));
+        assert_eq!(install.command.as_deref(), Some("pacman -S foo"));
+        assert!(install.outcome == Outcome::Unknown);
+        assert_eq!(
+            show_events(install),
+            ["Install foo - 1.0-1", "Install libfoo - 0.5-2"]
+        );
+        assert_eq!(install.events[1].time, local("2014-03-02 18:11"));
+        assert_eq!(remove.command.as_deref(), Some("pacman -R foo"));
+        assert_eq!(show_events(remove), ["Remove foo 1.0-1 -"]);
+    }
+
+    #[test]
+    fn missing_transaction_completed() {
+        let history = History::parse(
+            "[2024-01-15T10:00:00+0000] [PACMAN] Running 'pacman -S foo'\n\
+             [2024-01-15T10:00:01+0000] [ALPM] transaction started\n\
+             [2024-01-15T10:00:02+0000] [ALPM] installed foo (1.0-1)\n\
+             [2024-01-15T11:00:00+0000] [PACMAN] Running 'pacman -S bar'\n\
+             [2024-01-15T11:00:01+0000] [ALPM] transaction started\n\
+             [2024-01-15T11:00:02+0000] [ALPM] installed bar (1.0-1)\n\
+             [2024-01-15T11:00:03+0000] [ALPM] transaction failed\n\
+             [2024-01-15T12:00:00+0000] [PACMAN] Running 'pacman -S baz'\n\
+             [2024-01-15T12:00:01+0000] [ALPM] transaction started\n\
+             [2024-01-15T12:00:02+0000] [ALPM] installed baz (1.0-1)\n",
+        );
+        let outcomes: Vec<_> = history
+            .transactions
+            .iter()
+            .map(|trans| (trans.command.as_deref(), trans.outcome))
+            .collect();
+        assert_eq!(outcomes.len(), 3);
+        // Cut off by the next command, failed, and still running (or cut off by the end)
+        assert!(outcomes[0] == (Some("pacman -S foo"), Outcome::Unknown));
+        assert!(outcomes[1] == (Some("pacman -S bar"), Outcome::Failed));
+        assert!(outcomes[2] == (Some("pacman -S baz"), Outcome::Unknown));
+    }
+
+    #[test]
+    fn reinstall_and_remove_versions() {
+        let history = History::parse(
+            "[2024-01-15T10:00:01+0000] [ALPM] transaction started\n\
+             [2024-01-15T10:00:02+0000] [ALPM] reinstalled foo (1.0-1)\n\
+             [2024-01-15T10:00:03+0000] [ALPM] removed bar (2.0-1)\n\
+             [2024-01-15T10:00:04+0000] [ALPM] transaction completed\n",
+        );
+        let trans = &history.transactions[0];
+        assert_eq!(trans.command, None);
+        assert_eq!(
+            show_events(trans),
+            ["Reinstall foo 1.0-1 1.0-1", "Remove bar 2.0-1 -"]
+        );
+    }
+
+    #[test]
+    fn skips_other_lines() {
+        let history = History::parse(
+            "not a log line\n\
+             [2024-01-15T10:00:00+0000] [PACMAN] Running 'pacman -Sy'\n\
+             [2024-01-15T10:00:01+0000] [PACMAN] synchronizing package lists\n\
+             [2024-01-15T10:00:02+0000] [ALPM] transaction started\n\
+             [2024-01-15T10:00:03+0000] [ALPM] warning: something\n\
+             [2024-01-15T10:00:04+0000] [ALPM] transaction completed\n\
+             [bad time] [ALPM] installed foo (1.0-1)\n\
+             [2024-01-15T10:00:05+0000] [ALPM] installed foo 1.0-1\n",
+        );
+        // The only transaction has no package events
+        assert!(history.transactions.is_empty());
+    }
+
+    #[test]
+    fn pkg_events_across_transactions() {
+        let history = History::parse(
+            "[2024-01-15T10:00:00+0000] [ALPM] transaction started\n\
+             [2024-01-15T10:00:01+0000] [ALPM] installed foo (1.0-1)\n\
+             [2024-01-15T10:00:02+0000] [ALPM] installed bar (1.0-1)\n\
+             [2024-01-15T10:00:03+0000] [ALPM] transaction completed\n\
+             [2024-02-15T10:00:00+0000] [ALPM] transaction started\n\
+             [2024-02-15T10:00:01+0000] [ALPM] upgraded foo (1.0-1 -> 1.1-1)\n\
+             [2024-02-15T10:00:03+0000] [ALPM] transaction completed\n",
+        );
+        let versions: Vec<_> = history
+            .pkg_events("foo")
+            .map(|ev| ev.op.new_ver.as_deref())
+            .collect();
+        assert_eq!(versions, [Some("1.0-1"), Some("1.1-1")]);
+    }
+
+    #[test]
+    fn commands() {
+        let cmd = parse_command("pacman -S --asdeps --config /etc/p.conf core/foo bar");
+        assert_eq!(cmd.op, Some('S'));
+        assert!(cmd.reason == Some(Reason::Depend));
+        assert_eq!(cmd.targets, ["foo", "bar"]);
+        let cmd = parse_command("pacman -Ur /mnt ./foo-bar-1.0-1-x86_64.pkg.tar.zst");
+        assert_eq!(cmd.op, Some('U'));
+        assert!(cmd.reason.is_none());
+        assert_eq!(cmd.targets, ["foo-bar"]);
+        let cmd = parse_command("pacman -D --asexplicit -- baz");
+        assert_eq!(cmd.op, Some('D'));
+        assert!(cmd.reason == Some(Reason::Explicit));
+        assert_eq!(cmd.targets, ["baz"]);
+    }
+
+    #[test]
+    fn rollback_reasons() {
+        let history = History::parse(
+            "[2024-01-15T10:00:00+0000] [PACMAN] Running 'pacman -S foo'\n\
+             [2024-01-15T10:00:01+0000] [ALPM] transaction started\n\
+             [2024-01-15T10:00:02+0000] [ALPM] installed foo (1.0-1)\n\
+             [2024-01-15T10:00:02+0000] [ALPM] installed libfoo (1.0-1)\n\
+             [2024-01-15T10:00:02+0000] [ALPM] installed libbar (1.0-1)\n\
+             [2024-01-15T10:00:03+0000] [ALPM] transaction completed\n\
+             [2024-01-15T11:00:00+0000] [PACMAN] Running 'pacman -D --asexplicit libbar'\n\
+             [2024-01-15T11:30:00+0000] [ALPM] transaction started\n\
+             [2024-01-15T11:30:01+0000] [ALPM] installed other (1.0-1)\n\
+             [2024-01-15T11:30:02+0000] [ALPM] transaction completed\n\
+             [2024-01-15T12:00:00+0000] [PACMAN] Running 'pacman -Rs foo other'\n\
+             [2024-01-15T12:00:01+0000] [ALPM] transaction started\n\
+             [2024-01-15T12:00:02+0000] [ALPM] removed foo (1.0-1)\n\
+             [2024-01-15T12:00:02+0000] [ALPM] removed libfoo (1.0-1)\n\
+             [2024-01-15T12:00:02+0000] [ALPM] removed libbar (1.0-1)\n\
+             [2024-01-15T12:00:02+0000] [ALPM] removed other (1.0-1)\n\
+             [2024-01-15T12:00:03+0000] [ALPM] transaction completed\n",
+        );
+        assert_eq!(history.reason_changes.len(), 1);
+        // The -D command doesn't belong to the next transaction
+        assert_eq!(history.transactions[1].command, None);
+        let rollback = history.rollback(2, &PacState::default(), &[]);
+        let set_reasons: Vec<_> = rollback
+            .set_reasons
+            .iter()
+            .map(|(name, reason)| (name.as_str(), *reason == Reason::Depend))
+            .collect();
+        assert_eq!(set_reasons, [("libfoo", true)]);
+        assert!(
+            rollback
+                .notes
+                .iter()
+                .any(|note| note.starts_with("The log doesn't tell how other"))
+        );
+    }
+}
@@ -0,0 +1,474 @@
+use {
+    crate::{
+        alpm_util::{self, DepSpec, PkgId, Satisfier, Unsatisfied},
+        job::Job,
+    },
+    alpm::{DepMod, PackageReason, PackageValidation},
+    smol_str::SmolStr,
+    std::collections::{HashMap, VecDeque},
+};
+
+/// A snapshot of the local and sync package databases.
+///
+/// libalpm handles can't leave the thread that created them, so the databases are read into
+/// plain data on a worker thread, which the UI (and other workers) can then use freely.
+#[derive(Default)]
+pub struct PacState {
+    pub local_pkg_list: Vec<PkgInfo>,
+    /// The packages of every sync db, in pacman.conf order
+    pub remote_pkg_list: Vec<PkgInfo>,
+    local_index: PkgIndex,
+    remote_index: PkgIndex,
+    /// Sorted indices of the local packages that are orphans
+    local_orphans: Vec<usize>,
+}
+
+/// Lookup tables for a package list, so we don't have to scan it linearly
+#[derive(Default)]
+struct PkgIndex {
+    /// Indices of the packages with a given name (one per db), in list order
+    by_name: HashMap<SmolStr, Vec<usize>>,
+    /// Indices of the packages that provide a given name, in list order
+    by_provided: HashMap<SmolStr, Vec<usize>>,
+    /// Indices of the packages depending on each package
+    required_by: Vec<Vec<usize>>,
+    /// Indices of the packages optionally depending on each package
+    optional_for: Vec<Vec<usize>>,
+}
+
+impl PkgIndex {
+    fn new(list: &[PkgInfo]) -> Self {
+        let mut this = Self {
+            required_by: vec![Vec::new(); list.len()],
+            optional_for: vec![Vec::new(); list.len()],
+            ..Default::default()
+        };
+        for (i, pkg) in list.iter().enumerate() {
+            this.by_name.entry(pkg.name.clone()).or_default().push(i);
+            for prov in &pkg.provides {
+                this.by_provided
+                    .entry(prov.name.clone())
+                    .or_default()
+                    .push(i);
+            }
+        }
+        let mut required_by = std::mem::take(&mut this.required_by);
+        let mut optional_for = std::mem::take(&mut this.optional_for);
+        for (i, pkg) in list.iter().enumerate() {
+            for dep in &pkg.depends {
+                for sat in this.satisfiers(list, dep) {
+                    required_by[sat].push(i);
+                }
+            }
+            for dep in &pkg.optdepends {
+                for sat in this.satisfiers(list, dep) {
+                    optional_for[sat].push(i);
+                }
+            }
+        }
+        this.required_by = required_by;
+        this.optional_for = optional_for;
+        this
+    }
+    /// Indices of the packages that have the name of `dep` or provide it, in list order
+    fn candidates(&self, dep: &DepInfo) -> Vec<usize> {
+        let by_name = self.by_name.get(&dep.name).into_iter().flatten();
+        let by_provided = self.by_provided.get(&dep.name).into_iter().flatten();
+        let mut indices: Vec<usize> = by_name.chain(by_provided).copied().collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+    /// Indices of the packages that satisfy `dep`, in list order
+    fn satisfiers(&self, list: &[PkgInfo], dep: &DepInfo) -> Vec<usize> {
+        let spec = dep.spec();
+        let mut indices = self.candidates(dep);
+        indices.retain(|&i| spec.satisfied_by(&list[i]));
+        indices
+    }
+}
+
+/// Find the packages installed as dependencies that no explicitly installed package needs,
+/// directly or indirectly.
+///
+/// Unlike `pacman -Qdt`, this also finds groups of orphans that only require each other.
+fn find_orphans(list: &[PkgInfo], index: &PkgIndex) -> Vec<usize> {
+    let mut needed = vec![false; list.len()];
+    let mut stack = Vec::new();
+    for (i, pkg) in list.iter().enumerate() {
+        if pkg.reason == PackageReason::Explicit {
+            needed[i] = true;
+            stack.push(i);
+        }
+    }
+    while let Some(i) = stack.pop() {
+        for dep in &list[i].depends {
+            for sat in index.satisfiers(list, dep) {
+                if !needed[sat] {
+                    needed[sat] = true;
+                    stack.push(sat);
+                }
+            }
+        }
+    }
+    (0..list.len()).filter(|&i| !needed[i]).collect()
+}
+
+impl PacState {
+    pub fn load() -> anyhow::Result<Self> {
+        let alpm = alpm_util::alpm_handle()?;
+        let local_pkg_list: Vec<_> = alpm.localdb().pkgs().iter().map(PkgInfo::new).collect();
+        let remote_pkg_list: Vec<_> = alpm
+            .syncdbs()
+            .iter()
+            .flat_map(|db| db.pkgs())
+            .map(PkgInfo::new)
+            .collect();
+        let local_index = PkgIndex::new(&local_pkg_list);
+        Ok(Self {
+            local_orphans: find_orphans(&local_pkg_list, &local_index),
+            local_index,
+            remote_index: PkgIndex::new(&remote_pkg_list),
+            local_pkg_list,
+            remote_pkg_list,
+        })
+    }
+    pub fn spawn_load() -> Job<anyhow::Result<Self>> {
+        Job::spawn(Self::load)
+    }
+    fn list_and_index(&self, remote: bool) -> (&[PkgInfo], &PkgIndex) {
+        if remote {
+            (&self.remote_pkg_list, &self.remote_index)
+        } else {
+            (&self.local_pkg_list, &self.local_index)
+        }
+    }
+    pub fn find(&self, id: &PkgId) -> Option<&PkgInfo> {
+        let (list, index) = self.list_and_index(id.is_remote());
+        index
+            .by_name
+            .get(&id.name)?
+            .iter()
+            .map(|&i| &list[i])
+            .find(|pkg| pkg.db == id.db)
+    }
+    pub fn find_local(&self, name: &str) -> Option<&PkgInfo> {
+        let i = *self.local_index.by_name.get(name)?.first()?;
+        Some(&self.local_pkg_list[i])
+    }
+    /// The package named `name` in the first sync db (in pacman.conf order) that has one
+    pub fn find_remote(&self, name: &str) -> Option<&PkgInfo> {
+        let i = *self.remote_index.by_name.get(name)?.first()?;
+        Some(&self.remote_pkg_list[i])
+    }
+    /// Find the package that would satisfy `dep`, among the local packages, or the packages
+    /// of all sync dbs in pacman.conf order
+    pub fn resolve(&self, dep: &DepInfo, remote: bool) -> Result<&PkgInfo, Unsatisfied> {
+        let (list, index) = self.list_and_index(remote);
+        let candidates: Vec<&PkgInfo> = index.candidates(dep).iter().map(|&i| &list[i]).collect();
+        alpm_util::resolve_dep(dep.spec(), candidates.iter().copied())
+    }
+    /// All sync db packages satisfying `dep`, in pacman.conf order. For names found in several
+    /// sync dbs, only the first one is listed.
+    pub fn providers(&self, dep: &DepInfo) -> Vec<&PkgInfo> {
+        let list = &self.remote_pkg_list;
+        let mut pkgs: Vec<&PkgInfo> = Vec::new();
+        for i in self.remote_index.satisfiers(list, dep) {
+            if !pkgs.iter().any(|pkg| pkg.name == list[i].name) {
+                pkgs.push(&list[i]);
+            }
+        }
+        pkgs
+    }
+    pub fn local_orphans(&self) -> impl Iterator<Item = &PkgInfo> {
+        self.local_orphans.iter().map(|&i| &self.local_pkg_list[i])
+    }
+    pub fn is_local_orphan(&self, local_idx: usize) -> bool {
+        self.local_orphans.binary_search(&local_idx).is_ok()
+    }
+    /// The index of `pkg` in its list
+    fn index_of(&self, pkg: &PkgInfo) -> Option<usize> {
+        let (list, index) = self.list_and_index(!pkg.is_local());
+        index
+            .by_name
+            .get(&pkg.name)?
+            .iter()
+            .copied()
+            .find(|&i| list[i].db == pkg.db)
+    }
+    /// The packages in the same list as `pkg` that (optionally) depend on it, by name.
+    ///
+    /// For names found in several sync dbs, only the first one in pacman.conf order is listed.
+    pub fn dependents<'a>(&'a self, pkg: &PkgInfo, optional: bool) -> Vec<&'a PkgInfo> {
+        let (list, index) = self.list_and_index(!pkg.is_local());
+        let Some(i) = &self.index_of(pkg) else {
+            return Vec::new();
+        };
+        let dependents = if optional {
+            &index.optional_for[*i]
+        } else {
+            &index.required_by[*i]
+        };
+        let mut pkgs: Vec<&PkgInfo> = dependents.iter().map(|&i| &list[i]).collect();
+        // Stable, so the same names stay in db order
+        pkgs.sort_by(|a, b| a.name.cmp(&b.name));
+        pkgs.dedup_by(|a, b| a.name == b.name);
+        pkgs
+    }
+    /// Why the local package `pkg` is installed: the shortest chains of dependents leading from
+    /// it to each explicitly installed package that (transitively) needs it, shortest first.
+    ///
+    /// Without `optional`, only hard dependencies count, like for finding orphans, so there are
+    /// no chains exactly if `pkg` is an orphan. With `optional`, there are only the chains that go
+    /// through optional dependencies, to the explicitly installed packages that don't need `pkg`
+    /// otherwise.
+    ///
+    /// Each chain starts with `pkg`, and each link says whether the dependency on the previous
+    /// package is only optional. Empty if `pkg` is explicitly installed itself.
+    pub fn why_installed(&self, pkg: &PkgInfo, optional: bool) -> Vec<Vec<WhyLink<'_>>> {
+        let list = &self.local_pkg_list;
+        let Some(start) = self.index_of(pkg).filter(|_| pkg.is_local()) else {
+            return Vec::new();
+        };
+        if pkg.reason == PackageReason::Explicit {
+            return Vec::new();
+        }
+        let (parent, mut roots) = self.search_dependents(start, optional);
+        if optional {
+            let (_, hard_roots) = self.search_dependents(start, false);
+            roots.retain(|root| !hard_roots.contains(root));
+        }
+        roots
+            .into_iter()
+            .map(|root| {
+                let mut chain = Vec::new();
+                let mut i = root;
+                while let Some(&(prev, optional)) = parent.get(&i) {
+                    chain.push(WhyLink {
+                        pkg: &list[i],
+                        optional,
+                    });
+                    i = prev;
+                }
+                chain.push(WhyLink {
+                    pkg: &list[start],
+                    optional: false,
+                });
+                chain.reverse();
+                chain
+            })
+            .collect()
+    }
+    /// Search the (with `optional`, also optional) dependents of the local package `start`,
+    /// transitively, up to the explicitly installed ones.
+    ///
+    /// Returns where each package was reached from (and whether through an optional dependency),
+    /// and the explicitly installed packages reached, nearest first.
+    fn search_dependents(
+        &self,
+        start: usize,
+        optional: bool,
+    ) -> (HashMap<usize, (usize, bool)>, Vec<usize>) {
+        let list = &self.local_pkg_list;
+        let index = &self.local_index;
+        // Breadth-first, so the first path found to each package is a shortest one
+        let mut parent: HashMap<usize, (usize, bool)> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        let mut roots = Vec::new();
+        while let Some(i) = queue.pop_front() {
+            let dependents = index.required_by[i].iter().map(|&j| (j, false));
+            let opt_dependents = index.optional_for[i].iter().map(|&j| (j, true));
+            for (j, optional) in dependents.chain(opt_dependents.filter(|_| optional)) {
+                if j == start || parent.contains_key(&j) {
+                    continue;
+                }
+                parent.insert(j, (i, optional));
+                // An explicitly installed package is reason enough, no need to look further
+                if list[j].reason == PackageReason::Explicit {
+                    roots.push(j);
+                } else {
+                    queue.push_back(j);
+                }
+            }
+        }
+        (parent, roots)
+    }
+    /// The dependencies of `pkg`, or with `reverse`, the packages depending on it, among the
+    /// packages of the same list
+    pub fn dep_edges<'a>(
+        &'a self,
+        pkg: &'a PkgInfo,
+        reverse: bool,
+        optional: bool,
+    ) -> Vec<DepEdge<'a>> {
+        let mut edges = Vec::new();
+        if reverse {
+            let (list, index) = self.list_and_index(!pkg.is_local());
+            let Some(i) = self.index_of(pkg) else {
+                return edges;
+            };
+            let kinds = [(&index.required_by, false), (&index.optional_for, true)];
+            for (dependents, opt) in kinds.into_iter().take(if optional { 2 } else { 1 }) {
+                for &j in &dependents[i] {
+                    let dependent = &list[j];
+                    let deps = if opt {
+                        &dependent.optdepends
+                    } else {
+                        &dependent.depends
+                    };
+                    // Prefer the dependency naming `pkg` directly, if there is one
+                    let Some(dep) = deps.iter().find(|dep| dep.name == pkg.name).or_else(|| {
+                        deps.iter()
+                            .find(|dep| index.satisfiers(list, dep).contains(&i))
+                    }) else {
+                        continue;
+                    };
+                    edges.push(DepEdge {
+                        dep,
+                        pkg: Some(dependent),
+                        optional: opt,
+                        via_provides: dep.name != pkg.name,
+                    });
+                }
+            }
+        } else {
+            let remote = !pkg.is_local();
+            let optdepends = if optional { &pkg.optdepends[..] } else { &[] };
+            let deps = pkg.depends.iter().map(|dep| (dep, false));
+            for (dep, opt) in deps.chain(optdepends.iter().map(|dep| (dep, true))) {
+                let sat = self.resolve(dep, remote).ok();
+                edges.push(DepEdge {
+                    dep,
+                    pkg: sat,
+                    optional: opt,
+                    via_provides: sat.is_some_and(|sat| sat.name != dep.name),
+                });
+            }
+        }
+        edges
+    }
+}
+
+/// A link in a chain of dependents explaining why a package is installed
+pub struct WhyLink<'a> {
+    pub pkg: &'a PkgInfo,
+    /// `pkg` only optionally depends on the previous package of the chain
+    pub optional: bool,
+}
+
+/// A (possibly optional) dependency of one package on another
+pub struct DepEdge<'a> {
+    /// The dependency as declared by the depending package
+    pub dep: &'a DepInfo,
+    /// The package at the other end: the dependency, or in reverse, the dependent.
+    /// `None` if the dependency can't be satisfied.
+    pub pkg: Option<&'a PkgInfo>,
+    pub optional: bool,
+    /// The dependency is satisfied by a provision rather than by name
+    pub via_provides: bool,
+}
+
+pub struct PkgInfo {
+    pub db: SmolStr,
+    pub name: SmolStr,
+    pub version: SmolStr,
+    pub desc: Option<String>,
+    pub url: Option<String>,
+    pub isize: i64,
+    /// Size of the package file
+    pub download_size: i64,
+    pub build_date: i64,
+    pub install_date: Option<i64>,
+    pub arch: Option<String>,
+    pub packager: Option<String>,
+    pub licenses: Vec<String>,
+    pub groups: Vec<String>,
+    /// Only meaningful for local packages
+    pub reason: PackageReason,
+    /// How the package was validated when it was installed. Only meaningful for local packages.
+    pub validation: PackageValidation,
+    pub depends: Vec<DepInfo>,
+    pub optdepends: Vec<DepInfo>,
+    pub provides: Vec<DepInfo>,
+}
+
+impl Satisfier for PkgInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn version(&self) -> &str {
+        &self.version
+    }
+    fn provisions(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.provides
+            .iter()
+            .map(|prov| (prov.name.as_str(), prov.version.as_deref()))
+    }
+}
+
+impl PkgInfo {
+    fn new(pkg: &alpm::Package) -> Self {
+        Self {
+            db: pkg.db().map_or("<missing db>", |db| db.name()).into(),
+            name: pkg.name().into(),
+            version: pkg.version().as_str().into(),
+            desc: pkg.desc().map(Into::into),
+            url: pkg.url().map(Into::into),
+            isize: pkg.isize(),
+            download_size: pkg.size(),
+            build_date: pkg.build_date(),
+            install_date: pkg.install_date(),
+            arch: pkg.arch().map(Into::into),
+            packager: pkg.packager().map(Into::into),
+            licenses: pkg.licenses().iter().map(Into::into).collect(),
+            groups: pkg.groups().iter().map(Into::into).collect(),
+            reason: pkg.reason(),
+            validation: pkg.validation(),
+            depends: pkg.depends().iter().map(DepInfo::new).collect(),
+            optdepends: pkg.optdepends().iter().map(DepInfo::new).collect(),
+            provides: pkg.provides().iter().map(DepInfo::new).collect(),
+        }
+    }
+    pub fn is_local(&self) -> bool {
+        self.db == "local"
+    }
+    pub fn id(&self) -> PkgId {
+        PkgId::qualified(&self.db, &self.name)
+    }
+}
+
+/// A dependency, optional dependency, or provision of a package
+pub struct DepInfo {
+    pub name: SmolStr,
+    pub depmod: DepMod,
+    pub version: Option<SmolStr>,
+    pub desc: Option<String>,
+}
+
+impl DepInfo {
+    pub fn new(dep: &alpm::Dep) -> Self {
+        Self {
+            name: dep.name().into(),
+            depmod: dep.depmod(),
+            version: dep.version().map(|ver| ver.as_str().into()),
+            desc: dep.desc().map(Into::into),
+        }
+    }
+    pub fn spec(&self) -> DepSpec<'_> {
+        DepSpec {
+            name: &self.name,
+            depmod: self.depmod,
+            version: self.version.as_deref(),
+        }
+    }
+}
+
+impl std::fmt::Display for DepInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.spec())?;
+        if let Some(desc) = &self.desc {
+            write!(f, ": {desc}")?;
+        }
+        Ok(())
+    }
+}
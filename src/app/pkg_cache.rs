@@ -0,0 +1,182 @@
+use {
+    super::pac_state::{DepInfo, PacState, PkgInfo},
+    crate::alpm_util::{self, Satisfier, parse_pkg_filename},
+    alpm::SigLevel,
+    smol_str::SmolStr,
+    std::{collections::HashSet, path::PathBuf},
+};
+
+/// A package archive in one of the cache directories
+pub struct CachedPkg {
+    pub path: PathBuf,
+    pub name: SmolStr,
+    pub version: SmolStr,
+    pub size: u64,
+}
+
+/// Find the package archives in the `CacheDir`s from pacman.conf, sorted by name and version
+pub fn scan() -> anyhow::Result<Vec<CachedPkg>> {
+    let conf = alpm_utils::config::Config::new()?;
+    let mut pkgs = Vec::new();
+    for dir in &conf.cache_dir {
+        // Not every configured cache dir has to exist
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(file) = file_name.to_str().and_then(parse_pkg_filename) else {
+                continue;
+            };
+            let Ok(md) = entry.metadata() else {
+                continue;
+            };
+            if md.is_file() {
+                pkgs.push(CachedPkg {
+                    path: entry.path(),
+                    name: file.name.into(),
+                    version: file.version.into(),
+                    size: md.len(),
+                });
+            }
+        }
+    }
+    pkgs.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| alpm::vercmp(a.version.as_str(), b.version.as_str()))
+    });
+    Ok(pkgs)
+}
+
+/// The cached archive of a package version
+pub fn find<'a>(pkgs: &'a [CachedPkg], name: &str, version: &str) -> Option<&'a CachedPkg> {
+    pkgs.iter()
+        .find(|pkg| pkg.name == name && pkg.version == version)
+}
+
+/// How a cached version relates to the local packages
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheStatus {
+    Installed,
+    /// Another version of the package is installed
+    Old,
+    Uninstalled,
+}
+
+impl CacheStatus {
+    pub const ALL: [Self; 3] = [Self::Installed, Self::Old, Self::Uninstalled];
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Installed => "Installed",
+            Self::Old => "Old",
+            Self::Uninstalled => "Uninstalled",
+        }
+    }
+}
+
+pub fn status(pac: &PacState, pkg: &CachedPkg) -> CacheStatus {
+    match pac.find_local(&pkg.name) {
+        Some(local) if local.version == pkg.version => CacheStatus::Installed,
+        Some(_) => CacheStatus::Old,
+        None => CacheStatus::Uninstalled,
+    }
+}
+
+/// The archives `paccache -rk<keep>` would remove: all but the `keep` newest versions of each
+/// package. Unlike paccache, the installed version is always kept.
+pub fn older_than_last<'a>(
+    pac: &PacState,
+    pkgs: &'a [CachedPkg],
+    keep: usize,
+) -> Vec<&'a CachedPkg> {
+    let mut out = Vec::new();
+    for group in pkgs.chunk_by(|a, b| a.name == b.name) {
+        // The same version can be in several cache dirs
+        let mut kept = HashSet::new();
+        for pkg in group.iter().rev() {
+            if kept.contains(&pkg.version) {
+                continue;
+            }
+            if kept.len() < keep {
+                kept.insert(&pkg.version);
+            } else if status(pac, pkg) != CacheStatus::Installed {
+                out.push(pkg);
+            }
+        }
+    }
+    out
+}
+
+/// A cached archive of a package, with the metadata from its `.PKGINFO`
+pub struct CachedVersion {
+    pub path: PathBuf,
+    pub name: SmolStr,
+    pub version: SmolStr,
+    pub size: u64,
+    /// `None` if the archive couldn't be read
+    pub build_date: Option<i64>,
+    pub provides: Vec<DepInfo>,
+}
+
+impl Satisfier for CachedVersion {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn version(&self) -> &str {
+        &self.version
+    }
+    fn provisions(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.provides
+            .iter()
+            .map(|prov| (prov.name.as_str(), prov.version.as_deref()))
+    }
+}
+
+/// The cached archives of a package, oldest version first
+pub fn versions(name: &str) -> anyhow::Result<Vec<CachedVersion>> {
+    let alpm = alpm_util::alpm_handle()?;
+    Ok(scan()?
+        .into_iter()
+        .filter(|pkg| pkg.name == name)
+        .map(|pkg| {
+            // Only the metadata is needed, so skip reading the file list and checking signatures
+            let loaded =
+                alpm.pkg_load(pkg.path.to_string_lossy().as_bytes(), false, SigLevel::NONE);
+            if let Err(e) = &loaded {
+                eprintln!("Failed to read {}: {e}", pkg.path.display());
+            }
+            let loaded = loaded.ok();
+            CachedVersion {
+                build_date: loaded.as_ref().map(|p| p.build_date()),
+                provides: loaded
+                    .as_ref()
+                    .map(|p| p.provides().iter().map(DepInfo::new).collect())
+                    .unwrap_or_default(),
+                path: pkg.path,
+                name: pkg.name,
+                version: pkg.version,
+                size: pkg.size,
+            }
+        })
+        .collect())
+}
+
+/// The dependencies of other local packages that the installed `pkg` satisfies, but `version`
+/// wouldn't, with the package that has each
+pub fn broken_deps<'a>(
+    pac: &'a PacState,
+    pkg: &PkgInfo,
+    version: &CachedVersion,
+) -> Vec<(&'a PkgInfo, &'a DepInfo)> {
+    let mut broken = Vec::new();
+    for dependent in pac.dependents(pkg, false) {
+        for dep in &dependent.depends {
+            let spec = dep.spec();
+            if spec.satisfied_by(pkg) && !spec.satisfied_by(version) {
+                broken.push((dependent, dep));
+            }
+        }
+    }
+    broken
+}
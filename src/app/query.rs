@@ -0,0 +1,694 @@
+//! The query language of the package list filters.
+//!
+//! A query is a list of terms, which are ANDed together unless separated by `OR` (or `|`).
+//! Terms can be negated with a leading `-`, `!` or `NOT`, and grouped with parentheses.
+//!
+//! A term is either a bare word, which matches the name, description or provisions of a package,
+//! or a `field:value` pair. `size` and `version` can also be compared with `<`, `<=`, `>`, `>=`
+//! and `=`. Values containing spaces can be quoted.
+//!
+//! Depending on the [`SearchMode`], bare words are matched as substrings, regexes, or fuzzily.
+//! They also determine the [`Rank`] of the matching packages.
+
+use {
+    super::pac_state::{DepInfo, PacState, PkgInfo},
+    alpm::PackageReason,
+    fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2},
+    regex::{Regex, RegexBuilder},
+    std::{
+        cmp::{Ordering, Reverse},
+        ops::Range,
+        sync::LazyLock,
+    },
+};
+
+static FUZZY_MATCHER: LazyLock<SkimMatcherV2> = LazyLock::new(SkimMatcherV2::default);
+
+pub const HELP: &str = "\
+Bare words match the name, description or provisions of a package
+
+Fields:
+  name:, desc:, repo:, packager:, license:, group:, url:
+  provides:, depends:, optdepends:
+  installed:yes/no/explicit/dep
+  size>100MiB (also <, <=, >=, =)
+  version>=1.2 (also <, <=, >, =)
+
+Terms are ANDed. Combine with OR (|), negate with - (! or NOT), group with ( )
+
+In regex mode, quote regexes containing spaces or parentheses";
+
+/// How bare words are matched
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum SearchMode {
+    #[default]
+    Substring,
+    Regex,
+    Fuzzy,
+}
+
+/// A parsed query
+#[derive(Clone)]
+pub enum Query {
+    Term(Term),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+/// The empty query, which matches everything
+impl Default for Query {
+    fn default() -> Self {
+        Self::And(Vec::new())
+    }
+}
+
+/// Text values are stored lowercase, and matched case-insensitively
+#[derive(Clone)]
+pub enum Term {
+    Bare(Bare),
+    Name(String),
+    Desc(String),
+    Repo(String),
+    Provides(String),
+    Depends(String),
+    OptDepends(String),
+    Packager(String),
+    License(String),
+    Group(String),
+    Url(String),
+    Installed(Installed),
+    Size(Cmp, i64),
+    Version(Cmp, String),
+}
+
+/// A bare word
+#[derive(Clone)]
+pub enum Bare {
+    Substring(String),
+    Regex(Regex),
+    /// Fuzzy match on the name, substring match on the rest
+    Fuzzy(String),
+}
+
+#[derive(Clone, Copy)]
+pub enum Installed {
+    Yes,
+    No,
+    Explicit,
+    Depend,
+}
+
+#[derive(Clone, Copy)]
+pub enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Cmp {
+    fn test(self, ord: Ordering) -> bool {
+        match self {
+            Self::Lt => ord.is_lt(),
+            Self::Le => ord.is_le(),
+            Self::Eq => ord.is_eq(),
+            Self::Ge => ord.is_ge(),
+            Self::Gt => ord.is_gt(),
+        }
+    }
+}
+
+pub struct QueryError {
+    pub msg: String,
+    /// Byte range of the offending part of the query
+    pub span: Range<usize>,
+}
+
+#[derive(Clone, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+struct Token {
+    kind: TokenKind,
+    span: Range<usize>,
+}
+
+fn lex(src: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        let kind = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' => TokenKind::LParen,
+            ')' => TokenKind::RParen,
+            '|' => TokenKind::Or,
+            '&' => TokenKind::And,
+            '-' | '!' => TokenKind::Not,
+            _ => {
+                let mut word = String::new();
+                let mut end = start;
+                let mut open_quote = None;
+                while let Some(&(i, c)) = chars.peek() {
+                    if open_quote.is_none() && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    }
+                    chars.next();
+                    end = i + c.len_utf8();
+                    if c == '"' {
+                        open_quote = match open_quote {
+                            Some(_) => None,
+                            None => Some(i),
+                        };
+                    } else {
+                        word.push(c);
+                    }
+                }
+                if let Some(quote) = open_quote {
+                    return Err(QueryError {
+                        msg: "Unterminated quote".into(),
+                        span: quote..end,
+                    });
+                }
+                let kind = match word.as_str() {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::Not,
+                    _ => TokenKind::Word(word),
+                };
+                tokens.push(Token {
+                    kind,
+                    span: start..end,
+                });
+                continue;
+            }
+        };
+        chars.next();
+        tokens.push(Token {
+            kind,
+            span: start..start + c.len_utf8(),
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser<'src> {
+    src: &'src str,
+    mode: SearchMode,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.pos).map(|tok| &tok.kind)
+    }
+    fn error(&self, msg: impl Into<String>, span: Range<usize>) -> QueryError {
+        QueryError {
+            msg: msg.into(),
+            span,
+        }
+    }
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut alts = vec![self.and()?];
+        while self.peek() == Some(&TokenKind::Or) {
+            self.pos += 1;
+            alts.push(self.and()?);
+        }
+        Ok(if alts.len() == 1 {
+            alts.remove(0)
+        } else {
+            Query::Or(alts)
+        })
+    }
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut terms = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                None | Some(TokenKind::RParen | TokenKind::Or) => break,
+                Some(TokenKind::And) => {
+                    self.pos += 1;
+                    terms.push(self.unary()?);
+                }
+                Some(_) => terms.push(self.unary()?),
+            }
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Query::And(terms)
+        })
+    }
+    fn unary(&mut self) -> Result<Query, QueryError> {
+        let Some(tok) = self.tokens.get(self.pos) else {
+            // Point at whatever came last, e.g. a dangling `OR`
+            let span = self.tokens.last().map_or(0..0, |tok| tok.span.clone());
+            return Err(self.error("Expected a term", span));
+        };
+        let (kind, span) = (tok.kind.clone(), tok.span.clone());
+        self.pos += 1;
+        match kind {
+            TokenKind::Not => Ok(Query::Not(Box::new(self.unary()?))),
+            TokenKind::LParen => {
+                let query = self.or()?;
+                if self.peek() != Some(&TokenKind::RParen) {
+                    return Err(self.error("Unclosed parenthesis", span));
+                }
+                self.pos += 1;
+                Ok(query)
+            }
+            TokenKind::Word(word) => parse_term(&word, self.mode)
+                .map(Query::Term)
+                .map_err(|msg| self.error(msg, span)),
+            TokenKind::RParen | TokenKind::And | TokenKind::Or => {
+                Err(self.error(format!("Unexpected `{}`", &self.src[span.clone()]), span))
+            }
+        }
+    }
+}
+
+const CMP_OPS: [(&str, Cmp); 5] = [
+    (">=", Cmp::Ge),
+    ("<=", Cmp::Le),
+    (">", Cmp::Gt),
+    ("<", Cmp::Lt),
+    ("=", Cmp::Eq),
+];
+
+fn parse_term(word: &str, mode: SearchMode) -> Result<Term, String> {
+    // Only treat the word as a field match if it looks like one, so regexes like `a{1,}=` are fine
+    let Some(field_end) = word
+        .find([':', '<', '>', '='])
+        .filter(|&end| end > 0 && word[..end].chars().all(|c| c.is_ascii_alphabetic()))
+    else {
+        return parse_bare(word, mode).map(Term::Bare);
+    };
+    let field = &word[..field_end];
+    let rest = &word[field_end..];
+    let (cmp, value) = match rest.strip_prefix(':') {
+        Some(value) => (None, value),
+        None => CMP_OPS
+            .iter()
+            .find_map(|(op, cmp)| rest.strip_prefix(op).map(|value| (Some(*cmp), value)))
+            .expect("rest starts with one of the operator characters"),
+    };
+    if value.is_empty() {
+        return Err(format!("Missing value for `{field}`"));
+    }
+    let text = |make: fn(String) -> Term| match cmp {
+        None => Ok(make(value.to_lowercase())),
+        Some(_) => Err(format!(
+            "`{field}` can only be matched with `{field}:value`"
+        )),
+    };
+    match field.to_lowercase().as_str() {
+        "name" => text(Term::Name),
+        "desc" | "description" => text(Term::Desc),
+        "repo" | "db" => text(Term::Repo),
+        "provides" => text(Term::Provides),
+        "depends" | "dep" => text(Term::Depends),
+        "optdepends" | "optdep" => text(Term::OptDepends),
+        "packager" => text(Term::Packager),
+        "license" => text(Term::License),
+        "group" => text(Term::Group),
+        "url" => text(Term::Url),
+        "installed" => {
+            let installed = match value.to_lowercase().as_str() {
+                "yes" | "true" => Installed::Yes,
+                "no" | "false" => Installed::No,
+                "explicit" => Installed::Explicit,
+                "dep" | "deps" | "depend" | "dependency" => Installed::Depend,
+                _ => return Err(format!("Expected yes, no, explicit or dep, not `{value}`")),
+            };
+            match cmp {
+                None => Ok(Term::Installed(installed)),
+                Some(_) => Err("`installed` can only be matched with `installed:value`".into()),
+            }
+        }
+        "size" => Ok(Term::Size(cmp.unwrap_or(Cmp::Eq), parse_size(value)?)),
+        "version" | "ver" => Ok(Term::Version(cmp.unwrap_or(Cmp::Eq), value.to_owned())),
+        _ => Err(format!("Unknown field `{field}`")),
+    }
+}
+
+fn parse_bare(word: &str, mode: SearchMode) -> Result<Bare, String> {
+    Ok(match mode {
+        SearchMode::Substring => Bare::Substring(word.to_lowercase()),
+        SearchMode::Regex => Bare::Regex(
+            RegexBuilder::new(word)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| {
+                    // The full message spans several lines, pointing at the error
+                    let e = e.to_string();
+                    format!("Invalid regex: {}", e.lines().last().unwrap_or_default())
+                })?,
+        ),
+        SearchMode::Fuzzy => Bare::Fuzzy(word.to_lowercase()),
+    })
+}
+
+/// Parse sizes like `100`, `1.5MiB` or `20kb`
+fn parse_size(value: &str) -> Result<i64, String> {
+    let num_end = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(num_end);
+    let num: f64 = num.parse().map_err(|_| format!("Invalid size `{value}`"))?;
+    let mult: f64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "k" | "kib" => 1024.0,
+        "m" | "mib" => 1024.0 * 1024.0,
+        "g" | "gib" => 1024.0 * 1024.0 * 1024.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        _ => return Err(format!("Unknown size unit `{unit}`")),
+    };
+    Ok((num * mult) as i64)
+}
+
+impl Query {
+    pub fn parse(src: &str, mode: SearchMode) -> Result<Self, QueryError> {
+        let tokens = lex(src)?;
+        if tokens.is_empty() {
+            return Ok(Self::default());
+        }
+        let mut parser = Parser {
+            src,
+            mode,
+            tokens,
+            pos: 0,
+        };
+        let query = parser.or()?;
+        if let Some(tok) = parser.tokens.get(parser.pos) {
+            return Err(parser.error(
+                format!("Unexpected `{}`", &src[tok.span.clone()]),
+                tok.span.clone(),
+            ));
+        }
+        Ok(query)
+    }
+    pub fn matches(&self, pkg: &PkgInfo, pac: &PacState) -> bool {
+        match self {
+            Self::Term(term) => term.matches(pkg, pac),
+            Self::Not(query) => !query.matches(pkg, pac),
+            Self::And(queries) => queries.iter().all(|query| query.matches(pkg, pac)),
+            Self::Or(queries) => queries.iter().any(|query| query.matches(pkg, pac)),
+        }
+    }
+    /// How well `pkg` matches the (non-negated) bare words of the query
+    pub fn rank(&self, pkg: &PkgInfo) -> Rank {
+        match self {
+            Self::Term(Term::Bare(bare)) => bare.rank(pkg),
+            Self::Term(_) | Self::Not(_) => Rank::NONE,
+            Self::And(queries) | Self::Or(queries) => queries
+                .iter()
+                .map(|query| query.rank(pkg))
+                .min()
+                .unwrap_or(Rank::NONE),
+        }
+    }
+}
+
+/// Search relevance. Lower is better.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Rank {
+    tier: Tier,
+    /// Fuzzy match score
+    score: Reverse<i64>,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Tier {
+    ExactName,
+    NamePrefix,
+    NameSubstring,
+    NameFuzzy,
+    Other,
+    /// Not ranked by a bare word at all
+    None,
+}
+
+impl Rank {
+    const NONE: Self = Self::tier(Tier::None);
+    const fn tier(tier: Tier) -> Self {
+        Self {
+            tier,
+            score: Reverse(0),
+        }
+    }
+}
+
+impl Bare {
+    fn matches(&self, pkg: &PkgInfo) -> bool {
+        match self {
+            Self::Substring(s) => {
+                pkg.name.contains(s.as_str())
+                    || pkg
+                        .desc
+                        .as_deref()
+                        .is_some_and(|desc| desc.to_lowercase().contains(s))
+                    || pkg.provides.iter().any(|dep| dep.name.contains(s.as_str()))
+            }
+            Self::Regex(re) => {
+                re.is_match(&pkg.name)
+                    || pkg.desc.as_deref().is_some_and(|desc| re.is_match(desc))
+                    || pkg.provides.iter().any(|dep| re.is_match(&dep.name))
+            }
+            Self::Fuzzy(s) => {
+                FUZZY_MATCHER.fuzzy_match(&pkg.name, s).is_some()
+                    || pkg
+                        .desc
+                        .as_deref()
+                        .is_some_and(|desc| desc.to_lowercase().contains(s))
+            }
+        }
+    }
+    fn rank(&self, pkg: &PkgInfo) -> Rank {
+        let name = pkg.name.as_str();
+        let tier = match self {
+            Self::Substring(s) | Self::Fuzzy(s) => {
+                if name == s {
+                    Tier::ExactName
+                } else if name.starts_with(s.as_str()) {
+                    Tier::NamePrefix
+                } else if name.contains(s.as_str()) {
+                    Tier::NameSubstring
+                } else if let Self::Fuzzy(s) = self
+                    && let Some(score) = FUZZY_MATCHER.fuzzy_match(name, s)
+                {
+                    return Rank {
+                        tier: Tier::NameFuzzy,
+                        score: Reverse(score),
+                    };
+                } else {
+                    Tier::Other
+                }
+            }
+            Self::Regex(re) => match re.find(name) {
+                Some(m) if m.len() == name.len() => Tier::ExactName,
+                Some(m) if m.start() == 0 => Tier::NamePrefix,
+                Some(_) => Tier::NameSubstring,
+                None => Tier::Other,
+            },
+        };
+        Rank::tier(tier)
+    }
+}
+
+impl Term {
+    fn matches(&self, pkg: &PkgInfo, pac: &PacState) -> bool {
+        let contains = |haystack: &str, needle: &str| haystack.to_lowercase().contains(needle);
+        let has_dep = |deps: &[DepInfo], name: &str| {
+            deps.iter().any(|dep| dep.name.eq_ignore_ascii_case(name))
+        };
+        match self {
+            Self::Bare(bare) => bare.matches(pkg),
+            Self::Name(s) => contains(&pkg.name, s),
+            Self::Desc(s) => pkg.desc.as_deref().is_some_and(|desc| contains(desc, s)),
+            Self::Repo(s) => pkg.db.eq_ignore_ascii_case(s),
+            Self::Provides(s) => has_dep(&pkg.provides, s),
+            Self::Depends(s) => has_dep(&pkg.depends, s),
+            Self::OptDepends(s) => has_dep(&pkg.optdepends, s),
+            Self::Packager(s) => pkg.packager.as_deref().is_some_and(|p| contains(p, s)),
+            Self::License(s) => pkg.licenses.iter().any(|license| contains(license, s)),
+            Self::Group(s) => pkg.groups.iter().any(|group| group.eq_ignore_ascii_case(s)),
+            Self::Url(s) => pkg.url.as_deref().is_some_and(|url| contains(url, s)),
+            Self::Installed(installed) => {
+                let local = if pkg.is_local() {
+                    Some(pkg)
+                } else {
+                    pac.find_local(&pkg.name)
+                };
+                match installed {
+                    Installed::Yes => local.is_some(),
+                    Installed::No => local.is_none(),
+                    Installed::Explicit => {
+                        local.is_some_and(|local| local.reason == PackageReason::Explicit)
+                    }
+                    Installed::Depend => {
+                        local.is_some_and(|local| local.reason == PackageReason::Depend)
+                    }
+                }
+            }
+            Self::Size(cmp, size) => cmp.test(pkg.isize.cmp(size)),
+            Self::Version(cmp, ver) => cmp.test(alpm::vercmp(pkg.version.as_str(), ver.as_str())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alpm::PackageValidation};
+
+    fn pkg(name: &str, version: &str) -> PkgInfo {
+        PkgInfo {
+            db: "extra".into(),
+            name: name.into(),
+            version: version.into(),
+            desc: None,
+            url: None,
+            isize: 0,
+            download_size: 0,
+            build_date: 0,
+            install_date: None,
+            arch: None,
+            packager: None,
+            licenses: Vec::new(),
+            groups: Vec::new(),
+            reason: PackageReason::Explicit,
+            validation: PackageValidation::empty(),
+            depends: Vec::new(),
+            optdepends: Vec::new(),
+            provides: Vec::new(),
+        }
+    }
+
+    fn parse(src: &str) -> Query {
+        match Query::parse(src, SearchMode::Substring) {
+            Ok(query) => query,
+            Err(e) => panic!("{src:?}: {} at {:?}", e.msg, e.span),
+        }
+    }
+
+    fn parse_err(src: &str) -> (String, Range<usize>) {
+        match Query::parse(src, SearchMode::Substring) {
+            Ok(_) => panic!("{src:?} parsed"),
+            Err(e) => (e.msg, e.span),
+        }
+    }
+
+    /// The structure of a query, with bare words as themselves and other terms as `?`
+    fn show(query: &Query) -> String {
+        let list = |op: &str, queries: &[Query]| {
+            let queries: Vec<_> = queries.iter().map(show).collect();
+            format!("{op}({})", queries.join(" "))
+        };
+        match query {
+            Query::Term(Term::Bare(Bare::Substring(s))) => s.clone(),
+            Query::Term(_) => "?".into(),
+            Query::Not(query) => format!("!{}", show(query)),
+            Query::And(queries) => list("and", queries),
+            Query::Or(queries) => list("or", queries),
+        }
+    }
+
+    fn matches(src: &str, pkg: &PkgInfo) -> bool {
+        parse(src).matches(pkg, &PacState::default())
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(show(&parse("a b | c")), "or(and(a b) c)");
+        assert_eq!(show(&parse("a | b c")), "or(a and(b c))");
+        assert_eq!(show(&parse("a AND b OR c & d")), "or(and(a b) and(c d))");
+        assert_eq!(show(&parse("(a | b) c")), "and(or(a b) c)");
+        assert_eq!(show(&parse("a (b | (c d))")), "and(a or(b and(c d)))");
+        assert_eq!(show(&parse("")), "and()");
+    }
+
+    #[test]
+    fn negation() {
+        assert_eq!(show(&parse("-a b")), "and(!a b)");
+        assert_eq!(show(&parse("NOT (a | b)")), "!or(a b)");
+        assert_eq!(show(&parse("!-a")), "!!a");
+        let foo = pkg("foo", "1.0-1");
+        assert!(matches("-bar", &foo));
+        assert!(!matches("-foo", &foo));
+        assert!(matches("!(bar | baz)", &foo));
+        assert!(!matches("NOT (bar | foo)", &foo));
+    }
+
+    #[test]
+    fn paren_errors() {
+        assert_eq!(parse_err("(a b"), ("Unclosed parenthesis".into(), 0..1));
+        assert_eq!(parse_err("a (b (c)"), ("Unclosed parenthesis".into(), 2..3));
+        assert_eq!(parse_err("a )"), ("Unexpected `)`".into(), 2..3));
+        assert_eq!(parse_err("()"), ("Unexpected `)`".into(), 1..2));
+        assert_eq!(parse_err("a OR"), ("Expected a term".into(), 2..4));
+        assert_eq!(parse_err("a \"b c"), ("Unterminated quote".into(), 2..6));
+    }
+
+    #[test]
+    fn field_errors() {
+        assert_eq!(parse_err("a nope:x").1, 2..8);
+        assert_eq!(parse_err("name:").0, "Missing value for `name`");
+        assert_eq!(
+            parse_err("name>x").0,
+            "`name` can only be matched with `name:value`"
+        );
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("100"), Ok(100));
+        assert_eq!(parse_size("100b"), Ok(100));
+        assert_eq!(parse_size("1.5MiB"), Ok(1024 * 1024 * 3 / 2));
+        assert_eq!(parse_size("2G"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("20kb"), Ok(20_000));
+        assert_eq!(parse_size("3MB"), Ok(3_000_000));
+        assert!(parse_size("10XB").is_err());
+        assert!(parse_size("MiB").is_err());
+        let mut big = pkg("big", "1.0-1");
+        big.isize = 2 * 1024 * 1024;
+        assert!(matches("size>1MiB", &big));
+        assert!(matches("size<=2MiB", &big));
+        assert!(!matches("size<2MiB", &big));
+        assert!(!matches("size>2.1mb", &big));
+    }
+
+    #[test]
+    fn versions() {
+        let pkg = pkg("foo", "1.10-2");
+        // Compared like pacman does, not as strings
+        assert!(matches("version>1.9", &pkg));
+        assert!(matches("version>=1.10", &pkg));
+        assert!(matches("version=1.10-2", &pkg));
+        assert!(!matches("version=1.10-1", &pkg));
+        assert!(matches("ver<1.10.1", &pkg));
+        assert!(matches("version<1:0.1", &pkg));
+        assert!(matches("version:1.10-2", &pkg));
+    }
+
+    #[test]
+    fn ranks() {
+        let rank = |src: &str, name: &str| parse(src).rank(&pkg(name, "1"));
+        assert!(rank("foo", "foo") < rank("foo", "foobar"));
+        assert!(rank("foo", "foobar") < rank("foo", "libfoo"));
+        assert!(rank("foo", "libfoo") < rank("foo", "bar"));
+        assert!(rank("foo", "bar") < rank("name:foo", "foo"));
+        // Negated words don't count
+        assert!(rank("-foo", "foo") == rank("name:foo", "foo"));
+    }
+}
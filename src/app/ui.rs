@@ -1,20 +1,20 @@
 use {
-    super::{PacfrontApp, ouroboros_impl_pac_state::PacState},
-    cmd::CmdBuf,
+    super::{PacfrontApp, pac_state::PacState},
+    crate::{config::Config, trans::TransRequest},
+    cmd::{Cmd, CmdBuf},
     eframe::egui,
     egui_colors::Colorix,
     egui_dock::{DockArea, DockState},
-    std::{
-        io::{BufRead, BufReader},
-        process::{Command, ExitStatus, Stdio},
-        sync::mpsc::TryRecvError,
-    },
+    smol_str::SmolStr,
+    std::{collections::HashMap, process::Command, sync::Arc},
     tabs::{Tab, TabViewState},
+    trans::TransHandler,
 };
 
 pub mod cmd;
-mod paint_util;
+pub mod paint_util;
 mod tabs;
+pub mod trans;
 
 pub(super) struct UiState {
     dock_state: DockState<Tab>,
@@ -25,7 +25,23 @@ pub(super) struct UiState {
 pub struct SharedUiState {
     cmd: CmdBuf,
     pub colorix: Option<Colorix>,
-    pac_handler: Option<PacChildHandler>,
+    trans_handler: Option<TransHandler>,
+    pac_load_error: Option<String>,
+    /// Providers picked for dependencies in transaction previews, by dependency name
+    provider_choices: HashMap<SmolStr, SmolStr>,
+}
+
+impl UiState {
+    pub fn load_cfg(&mut self, cfg: &Config) {
+        for (_, tab) in self.dock_state.iter_all_tabs_mut() {
+            tab.load_cfg(cfg);
+        }
+    }
+    pub fn save_cfg(&self, cfg: &mut Config) {
+        for (_, tab) in self.dock_state.iter_all_tabs() {
+            tab.save_cfg(cfg);
+        }
+    }
 }
 
 impl Default for UiState {
@@ -37,25 +53,31 @@ impl Default for UiState {
     }
 }
 
-pub enum PacmanChildEvent {
-    Line(std::io::Result<String>),
-    Exit(std::io::Result<ExitStatus>),
-}
-
-type PacEventRecv = std::sync::mpsc::Receiver<PacmanChildEvent>;
-
-pub struct PacChildHandler {
-    recv: Option<PacEventRecv>,
-    exit_status: Option<ExitStatus>,
-    out_buf: String,
-}
-
-impl PacChildHandler {
-    pub fn new(recv: PacEventRecv) -> Self {
-        Self {
-            recv: Some(recv),
-            exit_status: None,
-            out_buf: String::new(),
+/// Swap in the package databases once they have been (re)loaded
+pub fn poll_pac_load(app: &mut PacfrontApp) {
+    let Some(job) = &mut app.pac_load else {
+        return;
+    };
+    if !job.poll() {
+        return;
+    }
+    match job.take_result() {
+        Some(Ok(pac)) => {
+            app.pac = Arc::new(pac);
+            app.pac_load = None;
+            app.ui.shared.pac_load_error = None;
+            for (_, tab) in app.ui.dock_state.iter_all_tabs_mut() {
+                tab.on_pac_reload(&app.pac);
+            }
+        }
+        Some(Err(e)) => {
+            eprintln!("Failed to load package databases: {e}");
+            app.ui.shared.pac_load_error = Some(format!("{e:#}"));
+            app.pac_load = None;
+        }
+        None => {
+            app.ui.shared.pac_load_error = Some("Loading stopped without a result".into());
+            app.pac_load = None;
         }
     }
 }
@@ -73,28 +95,63 @@ pub fn top_panel_ui(app: &mut PacfrontApp, ctx: &egui::Context) {
                 ui.menu_button("⟳ Sync", |ui| {
                     if ui.button("🔁 Sync databases (pacman -Sy)").clicked() {
                         ui.close_menu();
-                        let mut child = Command::new("pkexec")
-                            .stdout(Stdio::piped())
-                            .stderr(Stdio::piped())
-                            .args(["pacman", "-Sy"])
-                            .spawn()
-                            .unwrap();
-                        let (send, recv) = std::sync::mpsc::channel();
-                        app.ui.shared.pac_handler = Some(PacChildHandler::new(recv));
-                        let reader = BufReader::new(child.stdout.take().unwrap());
-                        let err_reader = BufReader::new(child.stderr.take().unwrap());
-                        let send2 = send.clone();
-                        std::thread::spawn(move || {
-                            for line in reader.lines() {
-                                send.send(PacmanChildEvent::Line(line)).unwrap();
-                            }
-                            send.send(PacmanChildEvent::Exit(child.wait())).unwrap();
-                        });
-                        std::thread::spawn(move || {
-                            for line in err_reader.lines() {
-                                send2.send(PacmanChildEvent::Line(line)).unwrap();
-                            }
-                        });
+                        app.ui
+                            .shared
+                            .cmd
+                            .push(Cmd::Transact(TransRequest::sync_dbs()));
+                    }
+                    if ui.button("🔁 Sync files databases (pacman -Fy)").clicked() {
+                        ui.close_menu();
+                        app.ui
+                            .shared
+                            .cmd
+                            .push(Cmd::Transact(TransRequest::sync_files()));
+                    }
+                    if ui.button("🔃 Reload package databases").clicked() {
+                        ui.close_menu();
+                        app.ui.shared.cmd.push(Cmd::ReloadPac);
+                    }
+                    if ui.button("⬆ Show updates").clicked() {
+                        ui.close_menu();
+                        app.ui
+                            .dock_state
+                            .push_to_first_leaf(Tab::Updates(Default::default()));
+                    }
+                });
+                ui.menu_button("🗂 Views", |ui| {
+                    if ui.button("👽 Foreign packages").clicked() {
+                        ui.close_menu();
+                        app.ui
+                            .dock_state
+                            .push_to_first_leaf(Tab::ForeignPkgs(Default::default()));
+                    }
+                    if ui.button("📂 File owners (pacman -Qo)").clicked() {
+                        ui.close_menu();
+                        app.ui
+                            .dock_state
+                            .push_to_first_leaf(Tab::FileOwners(Default::default()));
+                    }
+                    if ui.button("🕘 History (pacman.log)").clicked() {
+                        ui.close_menu();
+                        app.ui
+                            .dock_state
+                            .push_to_first_leaf(Tab::History(Default::default()));
+                    }
+                    if ui.button("💾 Package cache (paccache)").clicked() {
+                        ui.close_menu();
+                        app.ui
+                            .dock_state
+                            .push_to_first_leaf(Tab::Cache(Default::default()));
+                    }
+                    if ui.button("📝 Config files (pacdiff)").clicked() {
+                        ui.close_menu();
+                        app.ui.shared.cmd.push(Cmd::OpenConfigFiles);
+                    }
+                    if ui.button("🔎 File search (pacman -F)").clicked() {
+                        ui.close_menu();
+                        app.ui
+                            .dock_state
+                            .push_to_first_leaf(Tab::FileSearch(Default::default()));
                     }
                 });
                 ui.menu_button("☰ Preferences", |ui| {
@@ -115,64 +172,26 @@ pub fn top_panel_ui(app: &mut PacfrontApp, ctx: &egui::Context) {
                     }
                 });
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if app.ui.shared.pac_handler.is_some() {
+                    if app.pac_load.as_ref().is_some_and(|job| job.is_pending()) {
+                        ui.spinner();
+                        ui.label("loading package databases...");
+                    } else if let Some(e) = &app.ui.shared.pac_load_error {
+                        ui.colored_label(egui::Color32::RED, "⚠ Failed to load package databases")
+                            .on_hover_text(e);
+                    }
+                    if app
+                        .ui
+                        .shared
+                        .trans_handler
+                        .as_ref()
+                        .is_some_and(TransHandler::is_running)
+                    {
                         ui.spinner();
-                        ui.label("running pacman...");
+                        ui.label("running transaction...");
                     }
                 });
             });
         });
-    let mut close_handler = false;
-    if let Some(handler) = &mut app.ui.shared.pac_handler {
-        if let Some(recv) = handler.recv.as_mut() {
-            match recv.try_recv() {
-                Ok(ev) => match ev {
-                    PacmanChildEvent::Line(result) => {
-                        handler.out_buf.push_str(&result.unwrap());
-                        handler.out_buf.push('\n');
-                    }
-                    PacmanChildEvent::Exit(exit_status) => {
-                        handler.exit_status = Some(exit_status.unwrap())
-                    }
-                },
-                Err(TryRecvError::Empty) => {}
-                Err(TryRecvError::Disconnected) => {
-                    handler.recv = None;
-                }
-            }
-        }
-        if !handler.out_buf.is_empty() {
-            egui::Modal::new(egui::Id::new("pacman output modal")).show(ctx, |ui| {
-                ui.heading("Pacman output");
-                ui.separator();
-                let avail_rect = ui.ctx().available_rect();
-                let w = (avail_rect.width() * 0.5).round();
-                ui.set_width(w);
-                egui::ScrollArea::both()
-                    .max_height((avail_rect.height() * 0.5).round())
-                    .max_width(w)
-                    .show(ui, |ui| {
-                        ui.set_width(1000.0);
-                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-                        ui.add(
-                            egui::TextEdit::multiline(&mut handler.out_buf.as_str())
-                                .code_editor()
-                                .desired_width(f32::INFINITY),
-                        );
-                    });
-                ui.separator();
-                if let Some(status) = &handler.exit_status {
-                    ui.label(format!("Pacman exited ({status})"));
-                    if ui.button("Close").clicked() {
-                        close_handler = true;
-                    }
-                }
-            });
-        }
-    }
-    if close_handler {
-        app.ui.shared.pac_handler = None;
-    }
 }
 
 pub fn central_panel_ui(app: &mut PacfrontApp, ctx: &egui::Context) {
@@ -180,7 +199,35 @@ pub fn central_panel_ui(app: &mut PacfrontApp, ctx: &egui::Context) {
         .show_leaf_collapse_buttons(false)
         .show_leaf_close_all_buttons(false)
         .show(ctx, &mut TabViewState {
-            pac: &mut app.pac,
+            pac: &app.pac,
             ui: &mut app.ui.shared,
         });
 }
+
+/// Files dropped onto the window get looked up in the file owners tab
+pub fn file_drop_ui(app: &mut PacfrontApp, ctx: &egui::Context) {
+    let hovering = ctx.input(|inp| !inp.raw.hovered_files.is_empty());
+    if hovering {
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("file_drop_overlay"),
+        ));
+        let rect = ctx.screen_rect();
+        painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(160));
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "Drop to find the owning package",
+            egui::FontId::proportional(24.0),
+            egui::Color32::WHITE,
+        );
+    }
+    for file in ctx.input(|inp| inp.raw.dropped_files.clone()) {
+        if let Some(path) = file.path {
+            app.ui
+                .shared
+                .cmd
+                .push(Cmd::FindFileOwner(path.display().to_string()));
+        }
+    }
+}
@@ -1,8 +1,20 @@
 use {
-    super::{Tab, tabs::package::PkgTab},
-    crate::{alpm_util::PkgId, app::PacfrontApp},
+    super::{
+        Tab,
+        tabs::{
+            config_files::ConfigFilesState, dep_graph::DepGraphState, dep_tree::DepTreeState,
+            file_owners::FileOwnersState, package::PkgTab, verify::VerifyState,
+        },
+        trans::TransHandler,
+    },
+    crate::{
+        alpm_util::PkgId,
+        app::{PacfrontApp, pac_state::PacState},
+        trans::TransRequest,
+    },
     eframe::egui,
     egui_dock::{Node, NodeIndex, TabIndex},
+    smol_str::SmolStr,
 };
 
 #[derive(Default)]
@@ -18,6 +30,22 @@ impl CmdBuf {
 
 pub enum Cmd {
     OpenPkgTab(PkgId),
+    /// Open a dependency tree (or with `reverse`, a dependents tree) of a package
+    OpenDepTree {
+        id: PkgId,
+        reverse: bool,
+    },
+    /// Open a graph of the dependency neighbourhood of a package
+    OpenDepGraph(PkgId),
+    Transact(TransRequest),
+    /// Reload the package databases, e.g. after a transaction
+    ReloadPac,
+    /// Find out which package owns a path, in the file owners tab
+    FindFileOwner(String),
+    /// Open (or focus) the `.pacnew`/`.pacsave` file tab
+    OpenConfigFiles,
+    /// Check the files of these local packages
+    Verify(Vec<SmolStr>),
 }
 
 pub fn process_cmds(app: &mut PacfrontApp, _ctx: &egui::Context) {
@@ -25,44 +53,81 @@ pub fn process_cmds(app: &mut PacfrontApp, _ctx: &egui::Context) {
         match cmd {
             Cmd::OpenPkgTab(id) => {
                 // First, try to activate already existing tab for this package
-                let mut focus_indices = None;
-                for (node_idx, (surf_idx, node)) in
-                    app.ui.dock_state.iter_all_nodes_mut().enumerate()
-                {
-                    if let Node::Leaf { tabs, active, .. } = node {
-                        for (tab_idx, tab) in tabs.iter_mut().enumerate() {
-                            if let Tab::Pkg(pkg_tab) = tab
-                                && pkg_tab.id == id
-                            {
-                                focus_indices = Some((surf_idx, NodeIndex(node_idx)));
-                                *active = TabIndex(tab_idx);
-                            }
-                        }
-                    }
+                if !focus_tab(
+                    app,
+                    |tab| matches!(tab, Tab::Pkg(pkg_tab) if pkg_tab.id == id),
+                ) {
+                    push_tab(app, Tab::Pkg(PkgTab::new(id)));
                 }
-                // Determine where to place the new tab.
-                //
-                // For now, we just push to the last leaf node, and hope that's good enough.
-                #[expect(clippy::collapsible_else_if)]
-                if let Some(indices) = focus_indices {
-                    app.ui.dock_state.set_focused_node_and_surface(indices);
-                } else {
-                    if let Some(Node::Leaf { tabs, active, .. }) = app
-                        .ui
-                        .dock_state
-                        .main_surface_mut()
-                        .iter_mut()
-                        .rfind(|node| node.is_leaf())
-                    {
-                        tabs.push(Tab::Pkg(PkgTab::new(id)));
-                        *active = TabIndex(tabs.len().saturating_sub(1));
-                    } else {
-                        app.ui
-                            .dock_state
-                            .push_to_first_leaf(Tab::Pkg(PkgTab::new(id)));
+            }
+            Cmd::FindFileOwner(path) => {
+                let mut path = Some(path);
+                let found = focus_tab(app, |tab| match tab {
+                    Tab::FileOwners(state) => {
+                        state.push_query(path.take().unwrap_or_default());
+                        true
                     }
+                    _ => false,
+                });
+                if !found {
+                    let mut state = FileOwnersState::default();
+                    state.push_query(path.unwrap_or_default());
+                    push_tab(app, Tab::FileOwners(state));
                 }
             }
+            Cmd::OpenDepTree { id, reverse } => {
+                push_tab(app, Tab::DepTree(DepTreeState::new(id, reverse)));
+            }
+            Cmd::OpenDepGraph(id) => push_tab(app, Tab::DepGraph(DepGraphState::new(id))),
+            Cmd::OpenConfigFiles => {
+                if !focus_tab(app, |tab| matches!(tab, Tab::ConfigFiles(_))) {
+                    push_tab(app, Tab::ConfigFiles(ConfigFilesState::default()));
+                }
+            }
+            Cmd::Verify(pkgs) => push_tab(app, Tab::Verify(VerifyState::new(pkgs))),
+            Cmd::Transact(mut req) => {
+                // Remembered for the session, like answering pacman's provider prompt every time
+                req.providers = app.ui.shared.provider_choices.clone().into_iter().collect();
+                app.ui.shared.trans_handler = Some(TransHandler::new(req));
+            }
+            Cmd::ReloadPac => app.pac_load = Some(PacState::spawn_load()),
         }
     }
 }
+
+/// Activate the first tab for which `f` returns `true`. Returns whether there was one.
+fn focus_tab(app: &mut PacfrontApp, mut f: impl FnMut(&mut Tab) -> bool) -> bool {
+    let mut focus_indices = None;
+    for (node_idx, (surf_idx, node)) in app.ui.dock_state.iter_all_nodes_mut().enumerate() {
+        if let Node::Leaf { tabs, active, .. } = node
+            && let Some(tab_idx) = tabs.iter_mut().position(&mut f)
+        {
+            *active = TabIndex(tab_idx);
+            focus_indices = Some((surf_idx, NodeIndex(node_idx)));
+            break;
+        }
+    }
+    let Some(indices) = focus_indices else {
+        return false;
+    };
+    app.ui.dock_state.set_focused_node_and_surface(indices);
+    true
+}
+
+/// Open a new tab and activate it.
+///
+/// For now, we just push to the last leaf node, and hope that's good enough.
+fn push_tab(app: &mut PacfrontApp, tab: Tab) {
+    if let Some(Node::Leaf { tabs, active, .. }) = app
+        .ui
+        .dock_state
+        .main_surface_mut()
+        .iter_mut()
+        .rfind(|node| node.is_leaf())
+    {
+        tabs.push(tab);
+        *active = TabIndex(tabs.len().saturating_sub(1));
+    } else {
+        app.ui.dock_state.push_to_first_leaf(tab);
+    }
+}
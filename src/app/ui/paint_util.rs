@@ -34,3 +34,38 @@ pub fn draw_logo(painter: &egui::Painter, center: egui::Pos2, radius: f32) {
         egui::Stroke::new(1.0, egui::Color32::BLACK),
     );
 }
+
+/// Draw an arrow from `from` to `to`, optionally dashed
+pub fn draw_arrow(
+    painter: &egui::Painter,
+    from: egui::Pos2,
+    to: egui::Pos2,
+    stroke: egui::Stroke,
+    dashed: bool,
+) {
+    let dir = (to - from).normalized();
+    let head_len = 8.0_f32.min((to - from).length() / 2.0);
+    let base = to - dir * head_len;
+    if dashed {
+        painter.extend(egui::Shape::dashed_line(&[from, base], stroke, 6.0, 4.0));
+    } else {
+        painter.line_segment([from, base], stroke);
+    }
+    let normal = dir.rot90() * head_len * 0.4;
+    painter.add(egui::epaint::PathShape::convex_polygon(
+        vec![to, base + normal, base - normal],
+        stroke.color,
+        egui::Stroke::NONE,
+    ));
+}
+
+/// The point where the line from the center of `rect` towards `toward` leaves `rect`
+pub fn rect_border_point(rect: egui::Rect, toward: egui::Pos2) -> egui::Pos2 {
+    let d = toward - rect.center();
+    if d == egui::Vec2::ZERO {
+        return rect.center();
+    }
+    let half = rect.size() / 2.0;
+    let t = (half.x / d.x.abs()).min(half.y / d.y.abs());
+    rect.center() + d * t.min(1.0)
+}
@@ -1,17 +1,52 @@
 use {
     super::{PacState, SharedUiState},
-    eframe::egui,
+    crate::{
+        app::{
+            pac_state::PkgInfo,
+            query::{self, Query, QueryError, SearchMode},
+        },
+        config::{Config, PkgTableConfig},
+        job::Job,
+    },
+    alpm::PackageReason,
+    cache::CacheState,
+    config_files::ConfigFilesState,
+    dep_graph::DepGraphState,
+    dep_tree::DepTreeState,
+    eframe::{
+        egui,
+        epaint::text::{LayoutJob, TextFormat},
+    },
     egui_dock::TabViewer,
+    file_owners::FileOwnersState,
+    file_search::FileSearchState,
+    foreign::ForeignPkgsState,
+    history::HistoryState,
     package::PkgTab,
+    smol_str::SmolStr,
+    std::{collections::HashSet, sync::Arc},
+    updates::UpdatesState,
+    verify::VerifyState,
 };
 
+pub mod cache;
 mod color_theme;
+pub mod config_files;
+pub mod dep_graph;
+pub mod dep_tree;
+pub mod file_owners;
+pub mod file_search;
+pub mod foreign;
+pub mod history;
 pub mod local_pkg_list;
 pub mod package;
+mod pkg_table;
 pub mod remote_pkg_list;
+pub mod updates;
+pub mod verify;
 
 pub struct TabViewState<'pac, 'ui> {
-    pub pac: &'pac mut PacState,
+    pub pac: &'pac Arc<PacState>,
     pub ui: &'ui mut SharedUiState,
 }
 
@@ -20,21 +55,33 @@ impl TabViewer for TabViewState<'_, '_> {
 
     fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
         match tab {
-            Tab::LocalPkgList(_) => format!(
-                "Local packages ({})",
-                self.pac.borrow_local_pkg_list().len()
-            )
-            .into(),
-            Tab::RemotePkgList(_) => format!(
-                "Remote packages ({})",
-                self.pac
-                    .borrow_sync()
-                    .iter()
-                    .map(|db| db.pkgs().len())
-                    .sum::<usize>()
-            )
-            .into(),
+            Tab::LocalPkgList(_) => {
+                format!("Local packages ({})", self.pac.local_pkg_list.len()).into()
+            }
+            Tab::RemotePkgList(_) => {
+                format!("Remote packages ({})", self.pac.remote_pkg_list.len()).into()
+            }
+            Tab::Pkg(pkg) if pkg.missing => format!("⚠ 📦 {}", pkg.id).into(),
             Tab::Pkg(pkg) => format!("📦 {}", pkg.id).into(),
+            Tab::Updates(state) => match state.count() {
+                Some(n) => format!("⬆ Updates ({n})").into(),
+                None => "⬆ Updates".into(),
+            },
+            Tab::DepTree(state) if state.opts.reverse => format!("🌳 ⬆ {}", state.id).into(),
+            Tab::DepTree(state) => format!("🌳 {}", state.id).into(),
+            Tab::DepGraph(state) => format!("🕸 {}", state.id).into(),
+            Tab::ForeignPkgs(state) => {
+                format!("👽 Foreign packages ({})", state.pkgs(self.pac).len()).into()
+            }
+            Tab::FileOwners(_) => "📂 File owners".into(),
+            Tab::FileSearch(_) => "🔎 File search".into(),
+            Tab::ConfigFiles(_) => "📝 Config files".into(),
+            Tab::History(_) => "🕘 History".into(),
+            Tab::Cache(_) => "💾 Package cache".into(),
+            Tab::Verify(state) => match state.pkgs.as_slice() {
+                [name] => format!("🩺 Verify {name}").into(),
+                pkgs => format!("🩺 Verify ({} packages)", pkgs.len()).into(),
+            },
             Tab::ColorTheme => "🎨 Color theme".into(),
         }
     }
@@ -44,6 +91,16 @@ impl TabViewer for TabViewState<'_, '_> {
             Tab::LocalPkgList(state) => local_pkg_list::ui(ui, self.pac, self.ui, state),
             Tab::RemotePkgList(state) => remote_pkg_list::ui(ui, self.pac, self.ui, state),
             Tab::Pkg(tab) => package::ui(ui, self.pac, self.ui, tab),
+            Tab::Updates(state) => updates::ui(ui, self.ui, state),
+            Tab::DepTree(state) => dep_tree::ui(ui, self.pac, self.ui, state),
+            Tab::DepGraph(state) => dep_graph::ui(ui, self.pac, self.ui, state),
+            Tab::ForeignPkgs(state) => foreign::ui(ui, self.pac, self.ui, state),
+            Tab::FileOwners(state) => file_owners::ui(ui, self.ui, state),
+            Tab::FileSearch(state) => file_search::ui(ui, self.pac, self.ui, state),
+            Tab::Verify(state) => verify::ui(ui, self.ui, state),
+            Tab::ConfigFiles(state) => config_files::ui(ui, self.ui, state),
+            Tab::History(state) => history::ui(ui, self.pac, self.ui, state),
+            Tab::Cache(state) => cache::ui(ui, self.pac, self.ui, state),
             Tab::ColorTheme => color_theme::ui(ui, &mut self.ui.colorix),
         }
     }
@@ -61,6 +118,16 @@ impl TabViewer for TabViewState<'_, '_> {
             Tab::LocalPkgList(_) => false,
             Tab::Pkg(pkg_tab) => pkg_tab.force_close,
             Tab::RemotePkgList(_) => false,
+            Tab::Updates(_) => false,
+            Tab::DepTree(_) => false,
+            Tab::DepGraph(_) => false,
+            Tab::ForeignPkgs(_) => false,
+            Tab::FileOwners(_) => false,
+            Tab::FileSearch(_) => false,
+            Tab::Verify(_) => false,
+            Tab::ConfigFiles(_) => false,
+            Tab::History(_) => false,
+            Tab::Cache(_) => false,
             Tab::ColorTheme => false,
         }
     }
@@ -70,6 +137,16 @@ pub enum Tab {
     LocalPkgList(PkgListState),
     RemotePkgList(PkgListState),
     Pkg(PkgTab),
+    Updates(UpdatesState),
+    DepTree(DepTreeState),
+    DepGraph(DepGraphState),
+    ForeignPkgs(ForeignPkgsState),
+    FileOwners(FileOwnersState),
+    FileSearch(FileSearchState),
+    Verify(VerifyState),
+    ConfigFiles(ConfigFilesState),
+    History(HistoryState),
+    Cache(CacheState),
     ColorTheme,
 }
 impl Tab {
@@ -79,9 +156,260 @@ impl Tab {
             Tab::RemotePkgList(PkgListState::default()),
         ]
     }
+    /// Bring the tab up to date after `pac` has been rebuilt
+    pub fn on_pac_reload(&mut self, pac: &Arc<PacState>) {
+        match self {
+            Tab::LocalPkgList(state) => state.on_pac_reload(pac, local_pkg_list::list),
+            Tab::RemotePkgList(state) => state.on_pac_reload(pac, remote_pkg_list::list),
+            Tab::Pkg(pkg_tab) => pkg_tab.on_pac_reload(pac),
+            Tab::Updates(state) => *state = UpdatesState::default(),
+            Tab::DepTree(state) => state.on_pac_reload(),
+            Tab::DepGraph(state) => state.on_pac_reload(),
+            Tab::ForeignPkgs(state) => state.on_pac_reload(),
+            Tab::FileOwners(state) => state.on_pac_reload(),
+            Tab::FileSearch(state) => state.on_pac_reload(),
+            Tab::Verify(_) => {}
+            Tab::ConfigFiles(state) => state.on_pac_reload(),
+            Tab::History(state) => state.on_pac_reload(),
+            Tab::Cache(state) => state.on_pac_reload(),
+            Tab::ColorTheme => {}
+        }
+    }
+    /// Take the table layouts from the config
+    pub fn load_cfg(&mut self, cfg: &Config) {
+        match self {
+            Tab::LocalPkgList(state) => state.table = cfg.local_pkg_table.clone(),
+            Tab::RemotePkgList(state) => state.table = cfg.remote_pkg_table.clone(),
+            _ => {}
+        }
+    }
+    pub fn save_cfg(&self, cfg: &mut Config) {
+        match self {
+            Tab::LocalPkgList(state) => cfg.local_pkg_table = state.table.clone(),
+            Tab::RemotePkgList(state) => cfg.remote_pkg_table = state.table.clone(),
+            _ => {}
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct PkgListState {
     filter_string: String,
+    search_mode: SearchMode,
+    /// The last query that parsed successfully
+    query: Query,
+    query_error: Option<QueryError>,
+    /// Indices of the packages that pass the filter
+    filtered: Vec<usize>,
+    filter_job: Option<Job<anyhow::Result<Vec<usize>>>>,
+    /// Why the last filter job failed
+    filter_error: Option<String>,
+    table: PkgTableConfig,
+    /// Only used by the local package list
+    reason_view: ReasonView,
+    /// Names of the packages selected for bulk actions (local package list only)
+    selected: HashSet<SmolStr>,
+}
+
+/// Which local packages to list, by install reason
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum ReasonView {
+    #[default]
+    All,
+    Explicit,
+    Depend,
+    Orphans,
+}
+
+impl ReasonView {
+    fn matches(self, pac: &PacState, idx: usize, pkg: &PkgInfo) -> bool {
+        match self {
+            Self::All => true,
+            Self::Explicit => pkg.reason == PackageReason::Explicit,
+            Self::Depend => pkg.reason == PackageReason::Depend,
+            Self::Orphans => pac.is_local_orphan(idx),
+        }
+    }
+}
+
+/// Gets the package list a list tab shows
+type ListFn = fn(&PacState) -> &[PkgInfo];
+
+/// Indices of the packages in `list` in `view` matching `query`, most relevant first
+fn filter_ranked(pac: &PacState, list: &[PkgInfo], view: ReasonView, query: &Query) -> Vec<usize> {
+    let mut hits: Vec<_> = list
+        .iter()
+        .enumerate()
+        .filter(|&(i, pkg)| view.matches(pac, i, pkg) && query.matches(pkg, pac))
+        .map(|(i, pkg)| (i, query.rank(pkg)))
+        .collect();
+    // Stable, so equally relevant packages stay in db order
+    hits.sort_by_key(|&(_, rank)| rank);
+    hits.into_iter().map(|(i, _)| i).collect()
+}
+
+impl PkgListState {
+    /// Filter (and sort) the package list on a worker thread
+    fn refilter(&mut self, pac: &Arc<PacState>, list: ListFn) {
+        let pac = Arc::clone(pac);
+        let query = self.query.clone();
+        let sort = self.table.sort;
+        let view = self.reason_view;
+        // Replacing a previous job discards its (now outdated) result
+        self.filter_job = Some(Job::spawn(move || {
+            let list = list(&pac);
+            let mut indices = filter_ranked(&pac, list, view, &query);
+            if let Some(sort) = sort {
+                pkg_table::sort(&pac, list, &mut indices, sort);
+            }
+            Ok(indices)
+        }));
+    }
+    fn on_pac_reload(&mut self, pac: &Arc<PacState>, list: ListFn) {
+        // The old indices don't mean anything for the new package lists
+        self.filtered.clear();
+        self.selected.retain(|name| pac.find_local(name).is_some());
+        self.refilter(pac, list);
+    }
+    /// Pick up the result of the filter job if it has finished
+    fn poll_filter(&mut self) {
+        if let Some(job) = &mut self.filter_job
+            && job.poll()
+        {
+            match job.take_result() {
+                Some(Ok(filtered)) => {
+                    self.filtered = filtered;
+                    self.filter_error = None;
+                }
+                Some(Err(e)) => {
+                    eprintln!("Failed to filter the package list: {e}");
+                    self.filtered.clear();
+                    self.filter_error = Some(format!("{e:#}"));
+                }
+                None => {
+                    self.filtered.clear();
+                    self.filter_error = Some("Filtering stopped without a result".into());
+                }
+            }
+            self.filter_job = None;
+        }
+    }
+    fn is_filtering(&self) -> bool {
+        self.filter_job.as_ref().is_some_and(Job::is_pending)
+    }
+    /// The query editor, with the offending part of an invalid query highlighted
+    fn filter_bar_ui(&mut self, ui: &mut egui::Ui, pac: &Arc<PacState>, list: ListFn) {
+        let err_span = self.query_error.as_ref().map(|e| e.span.clone());
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let normal = TextFormat::simple(
+                egui::TextStyle::Body.resolve(ui.style()),
+                ui.visuals().widgets.inactive.text_color(),
+            );
+            let mut job = LayoutJob::default();
+            match &err_span {
+                Some(span) if text.get(span.clone()).is_some() => {
+                    job.append(&text[..span.start], 0.0, normal.clone());
+                    job.append(
+                        &text[span.clone()],
+                        0.0,
+                        TextFormat {
+                            color: egui::Color32::RED,
+                            underline: egui::Stroke::new(1.0, egui::Color32::RED),
+                            ..normal.clone()
+                        },
+                    );
+                    job.append(&text[span.end..], 0.0, normal);
+                }
+                _ => job.append(text, 0.0, normal),
+            }
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+        let re = ui
+            .add(
+                egui::TextEdit::singleline(&mut self.filter_string)
+                    .hint_text("🔍 Filter")
+                    .desired_width(300.0)
+                    .layouter(&mut layouter),
+            )
+            .on_hover_text(query::HELP);
+        let mut mode_changed = false;
+        for (mode, label, hover) in [
+            (SearchMode::Regex, ".*", "Match bare words as regexes"),
+            (SearchMode::Fuzzy, "~", "Match package names fuzzily"),
+        ] {
+            if ui
+                .selectable_label(self.search_mode == mode, label)
+                .on_hover_text(hover)
+                .clicked()
+            {
+                self.search_mode = if self.search_mode == mode {
+                    SearchMode::Substring
+                } else {
+                    mode
+                };
+                mode_changed = true;
+            }
+        }
+        if re.changed() || mode_changed {
+            match Query::parse(&self.filter_string, self.search_mode) {
+                Ok(query) => {
+                    self.query = query;
+                    self.query_error = None;
+                    self.refilter(pac, list);
+                }
+                Err(e) => self.query_error = Some(e),
+            }
+        }
+        ui.spacing();
+        ui.label(format!("{} packages listed", self.filtered.len()));
+        if self.is_filtering() {
+            ui.spinner();
+        }
+        if let Some(e) = &self.query_error {
+            ui.colored_label(egui::Color32::RED, format!("⚠ {}", e.msg));
+        }
+        if let Some(e) = &self.filter_error {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Failed to filter the packages: {e}"),
+            );
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            pkg_table::columns_menu_ui(ui, &mut self.table);
+        });
+    }
+    /// The filter bar and the table of filtered packages.
+    ///
+    /// The local package list also gets install reason views and bulk actions.
+    fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        pac: &Arc<PacState>,
+        ui_state: &mut SharedUiState,
+        list: ListFn,
+        local: bool,
+    ) {
+        self.poll_filter();
+        egui::TopBottomPanel::top("top_panel").show_inside(ui, |ui| {
+            ui.horizontal(|ui| {
+                self.filter_bar_ui(ui, pac, list);
+            });
+            if local && local_pkg_list::reason_bar_ui(ui, pac, ui_state, self) {
+                self.refilter(pac, list);
+            }
+            ui.add_space(4.0);
+        });
+        if pkg_table::table_ui(
+            ui,
+            pac,
+            list(pac),
+            &self.filtered,
+            &mut self.table,
+            local.then_some(&mut self.selected),
+            &mut ui_state.cmd,
+        ) {
+            self.refilter(pac, list);
+        }
+    }
 }
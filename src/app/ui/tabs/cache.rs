@@ -0,0 +1,260 @@
+use {
+    crate::{
+        alpm_util::PkgId,
+        app::{
+            pkg_cache::{self, CacheStatus, CachedPkg},
+            ui::{PacState, SharedUiState, cmd::Cmd},
+        },
+        job::Job,
+        trans::TransRequest,
+    },
+    eframe::egui,
+    egui_extras::{Column, TableBuilder},
+    humansize::format_size,
+    std::{collections::HashSet, path::PathBuf},
+};
+
+/// The package archives in the cache directories, and cleaning them up like `paccache`
+pub struct CacheState {
+    scan: Option<Job<anyhow::Result<Vec<CachedPkg>>>>,
+    filter: String,
+    /// How many versions of each package "Keep last" leaves alone
+    keep: usize,
+    /// Archives picked for deletion
+    marked: HashSet<PathBuf>,
+}
+
+impl Default for CacheState {
+    fn default() -> Self {
+        Self {
+            scan: None,
+            filter: String::new(),
+            // Like paccache
+            keep: 3,
+            marked: HashSet::new(),
+        }
+    }
+}
+
+impl CacheState {
+    pub fn on_pac_reload(&mut self) {
+        // Probably after deleting archives, or installing new ones
+        self.scan = None;
+        self.marked.clear();
+    }
+}
+
+pub fn ui(ui: &mut egui::Ui, pac: &PacState, ui_state: &mut SharedUiState, state: &mut CacheState) {
+    let job = state
+        .scan
+        .get_or_insert_with(|| Job::spawn(pkg_cache::scan));
+    job.poll();
+    let pkgs = match job.result() {
+        Some(Ok(pkgs)) => pkgs,
+        Some(Err(e)) => {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Failed to scan the package cache: {e:#}"),
+            );
+            return;
+        }
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Scanning the package cache...");
+            });
+            return;
+        }
+    };
+    let statuses: Vec<CacheStatus> = pkgs.iter().map(|pkg| pkg_cache::status(pac, pkg)).collect();
+    let mut rescan = false;
+    egui::TopBottomPanel::top("cache_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut state.filter)
+                    .hint_text("🔍 Package")
+                    .desired_width(200.0),
+            );
+            rescan = ui.button("⟳ Rescan").clicked();
+            ui.separator();
+            let n_names = pkgs.chunk_by(|a, b| a.name == b.name).count();
+            let total: u64 = pkgs.iter().map(|pkg| pkg.size).sum();
+            ui.label(format!(
+                "{} archives of {n_names} packages, {}",
+                pkgs.len(),
+                format_size(total, humansize::BINARY)
+            ));
+            for status in CacheStatus::ALL {
+                let size: u64 = pkgs
+                    .iter()
+                    .zip(&statuses)
+                    .filter(|(_, s)| **s == status)
+                    .map(|(pkg, _)| pkg.size)
+                    .sum();
+                ui.separator();
+                ui.label(format!(
+                    "{}: {}",
+                    status.label(),
+                    format_size(size, humansize::BINARY)
+                ));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Mark");
+            if ui
+                .button("all but the last")
+                .on_hover_text("Like paccache -rk<N>, but the installed version is always kept")
+                .clicked()
+            {
+                for pkg in pkg_cache::older_than_last(pac, pkgs, state.keep) {
+                    state.marked.insert(pkg.path.clone());
+                }
+            }
+            ui.add(egui::DragValue::new(&mut state.keep).range(0..=100));
+            ui.label("versions");
+            ui.separator();
+            if ui
+                .button("uninstalled")
+                .on_hover_text("Like paccache -ruk0")
+                .clicked()
+            {
+                for (pkg, status) in pkgs.iter().zip(&statuses) {
+                    if *status == CacheStatus::Uninstalled {
+                        state.marked.insert(pkg.path.clone());
+                    }
+                }
+            }
+            ui.separator();
+            if ui.button("Clear marks").clicked() {
+                state.marked.clear();
+            }
+        });
+        if !state.marked.is_empty() {
+            let marked: Vec<&CachedPkg> = pkgs
+                .iter()
+                .filter(|pkg| state.marked.contains(&pkg.path))
+                .collect();
+            let reclaimed: u64 = marked.iter().map(|pkg| pkg.size).sum();
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} archives marked, {} would be freed",
+                    marked.len(),
+                    format_size(reclaimed, humansize::BINARY)
+                ));
+                if ui.button("🗑 Delete marked").clicked() {
+                    ui_state.cmd.push(Cmd::Transact(TransRequest {
+                        remove_cached: marked
+                            .iter()
+                            .map(|pkg| pkg.path.to_string_lossy().into_owned())
+                            .collect(),
+                        ..Default::default()
+                    }));
+                }
+            });
+        }
+        ui.add_space(4.0);
+    });
+    if rescan {
+        *state = CacheState {
+            keep: state.keep,
+            ..Default::default()
+        };
+        return;
+    }
+    if pkgs.is_empty() {
+        ui.label("The package cache is empty");
+        return;
+    }
+    let filt = state.filter.trim().to_ascii_lowercase();
+    let rows: Vec<usize> = (0..pkgs.len())
+        .filter(|&i| filt.is_empty() || pkgs[i].name.contains(filt.as_str()))
+        .collect();
+    TableBuilder::new(ui)
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::remainder())
+        .auto_shrink(false)
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .header(18.0, |mut row| {
+            for label in ["", "Package", "Version", "Status", "Size", "File"] {
+                row.col(|ui| {
+                    ui.label(label);
+                });
+            }
+        })
+        .body(|mut body| {
+            body.ui_mut().style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            body.rows(22.0, rows.len(), |mut row| {
+                let i = rows[row.index()];
+                let pkg = &pkgs[i];
+                let first_of_pkg = i == 0 || pkgs[i - 1].name != pkg.name;
+                row.col(|ui| {
+                    let mut marked = state.marked.contains(&pkg.path);
+                    if ui.checkbox(&mut marked, "").changed() {
+                        if marked {
+                            state.marked.insert(pkg.path.clone());
+                        } else {
+                            state.marked.remove(&pkg.path);
+                        }
+                    }
+                });
+                row.col(|ui| {
+                    if !first_of_pkg {
+                        return;
+                    }
+                    let id = match statuses[i] {
+                        CacheStatus::Uninstalled => pac.find_remote(&pkg.name).map(|pkg| pkg.id()),
+                        _ => Some(PkgId::local(&pkg.name)),
+                    };
+                    match id {
+                        Some(id) => {
+                            if ui.link(pkg.name.as_str()).clicked() {
+                                ui_state.cmd.push(Cmd::OpenPkgTab(id));
+                            }
+                        }
+                        None => {
+                            ui.label(pkg.name.as_str());
+                        }
+                    }
+                    if ui
+                        .small_button("🗑")
+                        .on_hover_text("Mark every version but the installed one")
+                        .clicked()
+                    {
+                        for (other, status) in pkgs.iter().zip(&statuses) {
+                            if other.name == pkg.name && *status != CacheStatus::Installed {
+                                state.marked.insert(other.path.clone());
+                            }
+                        }
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(pkg.version.as_str());
+                });
+                row.col(|ui| {
+                    let color = match statuses[i] {
+                        CacheStatus::Installed => egui::Color32::GREEN,
+                        CacheStatus::Old => ui.visuals().text_color(),
+                        CacheStatus::Uninstalled => egui::Color32::YELLOW,
+                    };
+                    ui.colored_label(color, statuses[i].label());
+                });
+                row.col(|ui| {
+                    ui.label(format_size(pkg.size, humansize::BINARY));
+                });
+                row.col(|ui| {
+                    let path = pkg.path.to_string_lossy();
+                    ui.label(path.as_ref()).context_menu(|ui| {
+                        if ui.button("Copy path").clicked() {
+                            ui.close_menu();
+                            ui.ctx().copy_text(path.clone().into_owned());
+                        }
+                    });
+                });
+            });
+        });
+}
@@ -0,0 +1,244 @@
+use {
+    crate::{
+        alpm_util::{PacFileKind, PkgId},
+        app::{
+            config_files::{DiffChunk, PacFile, find_pac_files, line_diff, merge},
+            ui::{SharedUiState, cmd::Cmd},
+        },
+        job::Job,
+        trans::{ConfigAction, TransRequest},
+    },
+    eframe::egui,
+};
+
+/// `.pacnew` and `.pacsave` files, with a diff against the current file, like `pacdiff`
+#[derive(Default)]
+pub struct ConfigFilesState {
+    scan: Option<Job<anyhow::Result<Vec<PacFile>>>>,
+    /// Index into the scan results
+    selected: Option<usize>,
+    diff: Option<DiffView>,
+}
+
+struct DiffView {
+    /// The `.pac*` file
+    path: String,
+    /// Current file on the left, `.pac*` file on the right
+    chunks: Result<Vec<DiffChunk>, String>,
+    /// For each chunk, whether the merge takes the `.pac*` side
+    take_new: Vec<bool>,
+}
+
+impl DiffView {
+    fn new(file: &PacFile) -> Self {
+        let read = |path: &str| {
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read {path}: {e}"))
+        };
+        let current = if file.target_exists {
+            read(&file.target)
+        } else {
+            Ok(String::new())
+        };
+        let chunks = current.and_then(|current| Ok(line_diff(&current, &read(&file.path)?)));
+        let n_chunks = chunks.as_ref().map_or(0, Vec::len);
+        Self {
+            path: file.path.clone(),
+            chunks,
+            take_new: vec![false; n_chunks],
+        }
+    }
+}
+
+impl ConfigFilesState {
+    pub fn on_pac_reload(&mut self) {
+        *self = Self::default();
+    }
+}
+
+pub fn ui(ui: &mut egui::Ui, ui_state: &mut SharedUiState, state: &mut ConfigFilesState) {
+    let job = state.scan.get_or_insert_with(|| Job::spawn(find_pac_files));
+    job.poll();
+    let files = match job.result() {
+        Some(Ok(files)) => files,
+        Some(Err(e)) => {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Failed to look for .pacnew files: {e:#}"),
+            );
+            return;
+        }
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Looking for .pacnew and .pacsave files in /etc...");
+            });
+            return;
+        }
+    };
+    let mut rescan = false;
+    egui::SidePanel::left("config_files_list").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} files", files.len()));
+            rescan = ui.button("⟳ Rescan").clicked();
+        });
+        ui.separator();
+        if files.is_empty() {
+            ui.label("No .pacnew or .pacsave files in /etc");
+        }
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (i, file) in files.iter().enumerate() {
+                if ui
+                    .selectable_label(state.selected == Some(i), &file.path)
+                    .clicked()
+                {
+                    state.selected = Some(i);
+                    state.diff = Some(DiffView::new(file));
+                }
+            }
+        });
+    });
+    if rescan {
+        *state = ConfigFilesState::default();
+        return;
+    }
+    let (Some(file), Some(diff)) = (state.selected.and_then(|i| files.get(i)), &mut state.diff)
+    else {
+        ui.label("Select a file to compare it with the current version");
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.heading(&file.target);
+        if let Some(owner) = &file.owner
+            && ui.link(format!("({owner})")).clicked()
+        {
+            ui_state.cmd.push(Cmd::OpenPkgTab(PkgId::local(owner)));
+        }
+    });
+    let explanation = match (file.kind, file.target_exists) {
+        (PacFileKind::New, _) => {
+            "The .pacnew file is the new packaged version, which pacman didn't install \
+             because the current file was modified."
+        }
+        (PacFileKind::Save, true) => {
+            "The .pacsave file is a modified version that was saved when its package was \
+             removed or changed."
+        }
+        (PacFileKind::Save, false) => {
+            "The .pacsave file is a modified version that was saved when its package was \
+             removed. There is no current file."
+        }
+    };
+    ui.label(explanation);
+    ui.horizontal(|ui| {
+        let mut action = None;
+        if ui
+            .button("Keep current")
+            .on_hover_text(format!("Delete {}", file.path))
+            .clicked()
+        {
+            action = Some(ConfigAction::Keep);
+        }
+        if ui
+            .button(format!("Replace with {}", pac_suffix(file)))
+            .on_hover_text(format!("Move {} to {}", file.path, file.target))
+            .clicked()
+        {
+            action = Some(ConfigAction::Replace);
+        }
+        if let Ok(chunks) = &diff.chunks
+            && ui
+                .button("Merge")
+                .on_hover_text("Write the merged version below to the current file")
+                .clicked()
+        {
+            action = Some(ConfigAction::Merge(merge(chunks, |i| diff.take_new[i])));
+        }
+        if let Some(action) = action {
+            ui_state.cmd.push(Cmd::Transact(TransRequest::config_file(
+                diff.path.clone(),
+                action,
+            )));
+        }
+    });
+    ui.separator();
+    let chunks = match &diff.chunks {
+        Ok(chunks) => chunks,
+        Err(e) => {
+            ui.colored_label(egui::Color32::RED, e);
+            return;
+        }
+    };
+    ui.horizontal(|ui| {
+        ui.label("For each change, pick the side to merge.");
+        if ui.button("All current").clicked() {
+            diff.take_new.fill(false);
+        }
+        if ui.button(format!("All {}", pac_suffix(file))).clicked() {
+            diff.take_new.fill(true);
+        }
+    });
+    diff_ui(ui, chunks, &mut diff.take_new, pac_suffix(file));
+}
+
+fn pac_suffix(file: &PacFile) -> &'static str {
+    match file.kind {
+        PacFileKind::New => ".pacnew",
+        PacFileKind::Save => ".pacsave",
+    }
+}
+
+/// The current file on the left and the `.pac*` file on the right, with a side picker
+/// between them for each change
+fn diff_ui(ui: &mut egui::Ui, chunks: &[DiffChunk], take_new: &mut [bool], suffix: &str) {
+    let removed = egui::Color32::from_rgba_unmultiplied(200, 60, 60, 60);
+    let added = egui::Color32::from_rgba_unmultiplied(60, 200, 60, 60);
+    let line_text = |line: Option<&String>, bg: egui::Color32| match line {
+        Some(line) => egui::RichText::new(line.trim_end_matches('\n'))
+            .monospace()
+            .background_color(bg),
+        None => egui::RichText::new("").monospace(),
+    };
+    egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+        egui::Grid::new("config_diff")
+            .num_columns(3)
+            .spacing([8.0, 0.0])
+            .show(ui, |ui| {
+                ui.strong("Current");
+                ui.label("");
+                ui.strong(suffix);
+                ui.end_row();
+                for (i, chunk) in chunks.iter().enumerate() {
+                    match chunk {
+                        DiffChunk::Same(lines) => {
+                            for line in lines {
+                                ui.label(line_text(Some(line), egui::Color32::TRANSPARENT));
+                                ui.label("");
+                                ui.label(line_text(Some(line), egui::Color32::TRANSPARENT));
+                                ui.end_row();
+                            }
+                        }
+                        DiffChunk::Changed { old, new } => {
+                            for row in 0..old.len().max(new.len()) {
+                                let (old_bg, new_bg) = if take_new[i] {
+                                    (egui::Color32::TRANSPARENT, added)
+                                } else {
+                                    (removed, egui::Color32::TRANSPARENT)
+                                };
+                                ui.label(line_text(old.get(row), old_bg));
+                                if row == 0 {
+                                    ui.horizontal(|ui| {
+                                        ui.selectable_value(&mut take_new[i], false, "⏴");
+                                        ui.selectable_value(&mut take_new[i], true, "⏵");
+                                    });
+                                } else {
+                                    ui.label("");
+                                }
+                                ui.label(line_text(new.get(row), new_bg));
+                                ui.end_row();
+                            }
+                        }
+                    }
+                }
+            });
+    });
+}
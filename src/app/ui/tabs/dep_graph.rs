@@ -0,0 +1,291 @@
+use {
+    crate::{
+        alpm_util::PkgId,
+        app::{
+            dep_graph::DepGraph,
+            ui::{PacState, SharedUiState, cmd::Cmd, paint_util},
+        },
+    },
+    eframe::egui,
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+    },
+};
+
+const LAYER_SPACING: f32 = 220.0;
+const ROW_SPACING: f32 = 32.0;
+
+/// A drawing of the dependency neighbourhood of a package
+pub struct DepGraphState {
+    pub id: PkgId,
+    depth: u32,
+    rdepth: u32,
+    optional: bool,
+    /// Built lazily from the package lists, along with the node positions
+    graph: Option<(DepGraph, Vec<egui::Pos2>)>,
+    pan: egui::Vec2,
+    zoom: f32,
+    /// Exported files get this path, plus an extension
+    export_path: String,
+    /// The result of the last export
+    export_msg: Option<Result<String, String>>,
+    /// An export to a file that already exists, waiting to be confirmed
+    pending_overwrite: Option<PathBuf>,
+}
+
+impl DepGraphState {
+    pub fn new(id: PkgId) -> Self {
+        let export_path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(format!("{}-deps", id.name))
+            .display()
+            .to_string();
+        Self {
+            id,
+            depth: 1,
+            rdepth: 1,
+            optional: false,
+            graph: None,
+            pan: egui::Vec2::ZERO,
+            zoom: 1.0,
+            export_path,
+            export_msg: None,
+            pending_overwrite: None,
+        }
+    }
+    pub fn on_pac_reload(&mut self) {
+        self.graph = None;
+    }
+}
+
+/// Save the graph to `path`, as DOT or GraphML depending on its extension
+fn export(graph: &DepGraph, path: &Path) -> Result<String, String> {
+    let data = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("dot") => graph.to_dot(),
+        _ => graph.to_graphml(),
+    };
+    match std::fs::write(path, data) {
+        Ok(()) => Ok(format!("Saved {}", path.display())),
+        Err(e) => {
+            eprintln!("Failed to export graph: {e}");
+            Err(format!("Failed to save {}: {e}", path.display()))
+        }
+    }
+}
+
+/// Put each layer in a column, sorted by name
+fn layout(graph: &DepGraph) -> Vec<egui::Pos2> {
+    let mut layers: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for (i, node) in graph.nodes.iter().enumerate() {
+        layers.entry(node.layer).or_default().push(i);
+    }
+    let mut positions = vec![egui::Pos2::ZERO; graph.nodes.len()];
+    for (layer, mut nodes) in layers {
+        nodes.sort_by(|&a, &b| graph.nodes[a].label.cmp(&graph.nodes[b].label));
+        let mid = (nodes.len() - 1) as f32 / 2.0;
+        for (row, node) in nodes.into_iter().enumerate() {
+            positions[node] = egui::pos2(
+                layer as f32 * LAYER_SPACING,
+                (row as f32 - mid) * ROW_SPACING,
+            );
+        }
+    }
+    positions
+}
+
+pub fn ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    state: &mut DepGraphState,
+) {
+    egui::TopBottomPanel::top("dep_graph_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            let mut changed = false;
+            ui.label("Dependency depth");
+            changed |= ui
+                .add(egui::DragValue::new(&mut state.depth).range(0..=16))
+                .changed();
+            ui.label("Dependent depth");
+            changed |= ui
+                .add(egui::DragValue::new(&mut state.rdepth).range(0..=16))
+                .changed();
+            changed |= ui
+                .checkbox(&mut state.optional, "Optional")
+                .on_hover_text("Include optional dependencies")
+                .changed();
+            if changed {
+                state.graph = None;
+            }
+            if ui.button("⟲ Reset view").clicked() {
+                state.pan = egui::Vec2::ZERO;
+                state.zoom = 1.0;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Export to");
+            ui.add(egui::TextEdit::singleline(&mut state.export_path).desired_width(300.0));
+            for (label, ext) in [("Save DOT", "dot"), ("Save GraphML", "graphml")] {
+                if ui.button(label).clicked()
+                    && let Some((graph, _)) = &state.graph
+                {
+                    let path = PathBuf::from(format!("{}.{ext}", state.export_path));
+                    if path.exists() {
+                        state.export_msg = None;
+                        state.pending_overwrite = Some(path);
+                    } else {
+                        state.export_msg = Some(export(graph, &path));
+                    }
+                }
+            }
+            if ui.button("📋 Copy DOT").clicked()
+                && let Some((graph, _)) = &state.graph
+            {
+                ui.ctx().copy_text(graph.to_dot());
+                state.export_msg = Some(Ok("Copied DOT to the clipboard".into()));
+            }
+            match &state.export_msg {
+                Some(Ok(msg)) => {
+                    ui.label(msg);
+                }
+                Some(Err(msg)) => {
+                    ui.colored_label(egui::Color32::RED, msg);
+                }
+                None => {}
+            }
+        });
+        if let Some(path) = &state.pending_overwrite {
+            let mut close = false;
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("⚠ {} already exists", path.display()),
+                );
+                if ui.button("Overwrite").clicked()
+                    && let Some((graph, _)) = &state.graph
+                {
+                    state.export_msg = Some(export(graph, path));
+                    close = true;
+                }
+                close |= ui.button("Cancel").clicked();
+            });
+            if close {
+                state.pending_overwrite = None;
+            }
+        }
+        ui.add_space(4.0);
+    });
+    if state.graph.is_none() {
+        state.graph =
+            DepGraph::neighbourhood(pac, &state.id, state.depth, state.rdepth, state.optional).map(
+                |graph| {
+                    let positions = layout(&graph);
+                    (graph, positions)
+                },
+            );
+    }
+    let Some((graph, positions)) = &state.graph else {
+        ui.label("<Unresolved package>");
+        return;
+    };
+    let (rect, response) =
+        ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+    // Pan by dragging, zoom with the scroll wheel (or pinching) around the pointer
+    if response.dragged() {
+        state.pan += response.drag_delta();
+    }
+    if let Some(pointer) = response.hover_pos() {
+        let (scroll, pinch) = ui.input(|inp| (inp.smooth_scroll_delta.y, inp.zoom_delta()));
+        let new_zoom = (state.zoom * pinch * (scroll * 0.002).exp()).clamp(0.1, 5.0);
+        if new_zoom != state.zoom {
+            let world = (pointer - rect.center() - state.pan) / state.zoom;
+            state.pan = pointer - rect.center() - world * new_zoom;
+            state.zoom = new_zoom;
+        }
+    }
+    let to_screen = |pos: egui::Pos2| rect.center() + state.pan + pos.to_vec2() * state.zoom;
+    let visuals = ui.visuals();
+    let font = egui::FontId::proportional(13.0 * state.zoom);
+    let galleys: Vec<_> = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let color = if node.id.is_some() {
+                visuals.text_color()
+            } else {
+                egui::Color32::RED
+            };
+            painter.layout_no_wrap(node.label.clone(), font.clone(), color)
+        })
+        .collect();
+    let rects: Vec<_> = positions
+        .iter()
+        .zip(&galleys)
+        .map(|(&pos, galley)| {
+            egui::Rect::from_center_size(
+                to_screen(pos),
+                galley.size() + egui::vec2(12.0, 6.0) * state.zoom,
+            )
+        })
+        .collect();
+    let hovered = response
+        .hover_pos()
+        .and_then(|pointer| rects.iter().position(|rect| rect.contains(pointer)));
+    for edge in &graph.edges {
+        let (from, to) = (rects[edge.from], rects[edge.to]);
+        let highlighted = hovered.is_some_and(|h| h == edge.from || h == edge.to);
+        let color = if highlighted {
+            visuals.selection.stroke.color
+        } else {
+            visuals.weak_text_color()
+        };
+        let start = paint_util::rect_border_point(from, to.center());
+        let end = paint_util::rect_border_point(to, from.center());
+        paint_util::draw_arrow(
+            &painter,
+            start,
+            end,
+            egui::Stroke::new(1.0, color),
+            edge.optional,
+        );
+        if let Some(prov) = &edge.provision
+            && state.zoom > 0.6
+        {
+            painter.text(
+                start.lerp(end, 0.5),
+                egui::Align2::CENTER_BOTTOM,
+                prov,
+                egui::FontId::proportional(10.0 * state.zoom),
+                color,
+            );
+        }
+    }
+    for (i, (node, galley)) in graph.nodes.iter().zip(galleys).enumerate() {
+        let fill = if node.layer == 0 {
+            visuals.selection.bg_fill
+        } else {
+            visuals.widgets.inactive.bg_fill
+        };
+        let stroke = if hovered == Some(i) {
+            visuals.selection.stroke
+        } else {
+            visuals.widgets.inactive.bg_stroke
+        };
+        painter.rect(rects[i], 4.0 * state.zoom, fill, stroke);
+        painter.galley(
+            rects[i].center() - galley.size() / 2.0,
+            galley,
+            visuals.text_color(),
+        );
+    }
+    if let Some(i) = hovered
+        && let Some(id) = &graph.nodes[i].id
+    {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        if response.clicked() {
+            ui_state.cmd.push(Cmd::OpenPkgTab(id.clone()));
+        }
+    }
+}
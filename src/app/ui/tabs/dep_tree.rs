@@ -0,0 +1,303 @@
+use {
+    crate::{
+        alpm_util::PkgId,
+        app::{
+            pac_state::{DepInfo, PkgInfo},
+            ui::{PacState, SharedUiState, cmd::Cmd},
+        },
+    },
+    eframe::egui::{self, collapsing_header::CollapsingState},
+    std::collections::HashSet,
+};
+
+/// A `pactree`-like view of the transitive dependencies (or dependents) of a package
+pub struct DepTreeState {
+    pub id: PkgId,
+    pub opts: TreeOpts,
+    /// Built lazily from the package lists
+    tree: Option<DepNode>,
+    /// Bumped by "expand/collapse all" to reset the open state of every node
+    open_gen: u32,
+    default_open: bool,
+}
+
+impl DepTreeState {
+    pub fn new(id: PkgId, reverse: bool) -> Self {
+        Self {
+            id,
+            opts: TreeOpts {
+                reverse,
+                optional: false,
+                max_depth: None,
+            },
+            tree: None,
+            open_gen: 0,
+            default_open: false,
+        }
+    }
+    pub fn on_pac_reload(&mut self) {
+        self.tree = None;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TreeOpts {
+    /// Show the packages depending on the root, like `pactree -r`
+    pub reverse: bool,
+    /// Include optional dependencies
+    optional: bool,
+    max_depth: Option<usize>,
+}
+
+struct DepNode {
+    /// The package, if the dependency could be resolved
+    pkg: Option<PkgId>,
+    /// The dependency as declared (the root just has its name)
+    dep: String,
+    optional: bool,
+    /// The name of the package satisfying the dependency through a provision
+    provider: Option<String>,
+    kind: NodeKind,
+    children: Vec<DepNode>,
+}
+
+#[derive(PartialEq)]
+enum NodeKind {
+    Expanded,
+    /// Already expanded elsewhere in the tree
+    Seen,
+    /// Has (possibly) more dependencies, but the depth limit was reached
+    Truncated,
+    Unresolved,
+}
+
+/// Build the tree depth-first, only expanding each package the first time it's encountered
+fn build(pac: &PacState, id: &PkgId, opts: TreeOpts) -> Option<DepNode> {
+    fn children(
+        pac: &PacState,
+        pkg: &PkgInfo,
+        depth: usize,
+        opts: TreeOpts,
+        seen: &mut HashSet<PkgId>,
+    ) -> Vec<DepNode> {
+        let mut nodes = Vec::new();
+        for edge in pac.dep_edges(pkg, opts.reverse, opts.optional) {
+            let Some(target) = edge.pkg else {
+                nodes.push(DepNode {
+                    pkg: None,
+                    dep: edge.dep.to_string(),
+                    optional: edge.optional,
+                    provider: None,
+                    kind: NodeKind::Unresolved,
+                    children: Vec::new(),
+                });
+                continue;
+            };
+            let id = target.id();
+            let (kind, grandchildren) = if seen.contains(&id) {
+                (NodeKind::Seen, Vec::new())
+            } else if opts.max_depth.is_some_and(|max| depth >= max) {
+                (NodeKind::Truncated, Vec::new())
+            } else {
+                seen.insert(id.clone());
+                let nodes = children(pac, target, depth + 1, opts, seen);
+                (NodeKind::Expanded, nodes)
+            };
+            // In reverse, the dependent is the node, and the edge is its dependency on the parent
+            let (dep, provider) = if opts.reverse {
+                let via = edge.via_provides.then(|| edge.dep.name.to_string());
+                (target.name.to_string(), via)
+            } else {
+                let via = edge.via_provides.then(|| target.name.to_string());
+                (dep_without_desc(edge.dep), via)
+            };
+            nodes.push(DepNode {
+                pkg: Some(id),
+                dep,
+                optional: edge.optional,
+                provider,
+                kind,
+                children: grandchildren,
+            });
+        }
+        nodes
+    }
+    let root = pac.find(id)?;
+    let mut seen = HashSet::from([id.clone()]);
+    let truncated = opts.max_depth == Some(0);
+    Some(DepNode {
+        pkg: Some(id.clone()),
+        dep: root.name.to_string(),
+        optional: false,
+        provider: None,
+        kind: if truncated {
+            NodeKind::Truncated
+        } else {
+            NodeKind::Expanded
+        },
+        children: if truncated {
+            Vec::new()
+        } else {
+            children(pac, root, 1, opts, &mut seen)
+        },
+    })
+}
+
+/// Optional dependencies have a description, which is too much for a tree node
+fn dep_without_desc(dep: &DepInfo) -> String {
+    let s = dep.to_string();
+    match &dep.desc {
+        Some(desc) => s
+            .strip_suffix(&format!(": {desc}"))
+            .unwrap_or(&s)
+            .to_owned(),
+        None => s,
+    }
+}
+
+fn count_expanded(node: &DepNode) -> usize {
+    let own = usize::from(node.kind == NodeKind::Expanded);
+    own + node.children.iter().map(count_expanded).sum::<usize>()
+}
+
+pub fn ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    state: &mut DepTreeState,
+) {
+    let mut changed = false;
+    egui::TopBottomPanel::top("dep_tree_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(&mut state.opts.reverse, "Reverse")
+                .on_hover_text("Show the packages depending on this one, like `pactree -r`")
+                .changed();
+            changed |= ui
+                .checkbox(&mut state.opts.optional, "Optional")
+                .on_hover_text("Include optional dependencies")
+                .changed();
+            let mut limited = state.opts.max_depth.is_some();
+            changed |= ui.checkbox(&mut limited, "Depth limit").changed();
+            match (limited, &mut state.opts.max_depth) {
+                (true, Some(depth)) => {
+                    changed |= ui.add(egui::DragValue::new(depth).range(0..=64)).changed();
+                }
+                (true, max_depth @ None) => *max_depth = Some(3),
+                (false, max_depth) => *max_depth = None,
+            }
+            ui.separator();
+            if ui.button("⊞ Expand all").clicked() {
+                state.open_gen += 1;
+                state.default_open = true;
+            }
+            if ui.button("⊟ Collapse all").clicked() {
+                state.open_gen += 1;
+                state.default_open = false;
+            }
+            if let Some(tree) = &state.tree {
+                ui.separator();
+                ui.label(format!(
+                    "{} packages",
+                    count_expanded(tree).saturating_sub(1)
+                ));
+            }
+        });
+        ui.add_space(4.0);
+    });
+    if changed {
+        state.tree = None;
+    }
+    let tree = state.tree.get_or_insert_with(|| {
+        build(pac, &state.id, state.opts).unwrap_or(DepNode {
+            pkg: None,
+            dep: state.id.to_string(),
+            optional: false,
+            provider: None,
+            kind: NodeKind::Unresolved,
+            children: Vec::new(),
+        })
+    });
+    let id = ui.id().with(("dep_tree", state.open_gen));
+    egui::ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+        node_ui(
+            ui,
+            tree,
+            id,
+            true,
+            state.default_open,
+            state.opts.reverse,
+            ui_state,
+        );
+    });
+}
+
+fn node_ui(
+    ui: &mut egui::Ui,
+    node: &DepNode,
+    id: egui::Id,
+    open: bool,
+    default_open: bool,
+    reverse: bool,
+    ui_state: &mut SharedUiState,
+) {
+    let header = |ui: &mut egui::Ui, ui_state: &mut SharedUiState| {
+        match &node.pkg {
+            Some(pkg) => {
+                if ui.link(&node.dep).clicked() {
+                    ui_state.cmd.push(Cmd::OpenPkgTab(pkg.clone()));
+                }
+            }
+            None => {
+                ui.colored_label(egui::Color32::RED, &node.dep);
+            }
+        }
+        if let Some(provider) = &node.provider {
+            if reverse {
+                ui.weak(format!("(via {provider})"))
+                    .on_hover_text("Depends on a name this package provides");
+            } else {
+                ui.weak(format!("(provided by {provider})"));
+            }
+        }
+        if node.optional {
+            ui.weak("[optional]");
+        }
+        match node.kind {
+            NodeKind::Expanded => {}
+            NodeKind::Seen => {
+                ui.weak("⟲")
+                    .on_hover_text("Already shown elsewhere in the tree");
+            }
+            NodeKind::Truncated => {
+                ui.weak("…").on_hover_text("Depth limit reached");
+            }
+            NodeKind::Unresolved => {
+                ui.colored_label(egui::Color32::RED, "(unresolved)");
+            }
+        }
+    };
+    if node.children.is_empty() {
+        ui.horizontal(|ui| {
+            // Line up with the nodes that have a collapse button
+            ui.add_space(ui.spacing().icon_width + ui.spacing().item_spacing.x);
+            header(ui, ui_state);
+        });
+        return;
+    }
+    CollapsingState::load_with_default_open(ui.ctx(), id, open || default_open)
+        .show_header(ui, |ui| header(ui, ui_state))
+        .body(|ui| {
+            for (i, child) in node.children.iter().enumerate() {
+                node_ui(
+                    ui,
+                    child,
+                    id.with(i),
+                    false,
+                    default_open,
+                    reverse,
+                    ui_state,
+                );
+            }
+        });
+}
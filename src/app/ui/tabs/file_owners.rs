@@ -0,0 +1,126 @@
+use {
+    crate::{
+        alpm_util::PkgId,
+        app::{
+            file_index::{FileIndex, owner_query_path},
+            ui::{SharedUiState, cmd::Cmd},
+        },
+        job::Job,
+    },
+    eframe::egui,
+};
+
+/// Look up which local packages own a path, like `pacman -Qo`
+#[derive(Default)]
+pub struct FileOwnersState {
+    query: String,
+    /// Built when the first query comes in
+    index: Option<Job<anyhow::Result<FileIndex>>>,
+    /// Queries waiting for the index
+    pending: Vec<String>,
+    /// Most recent first
+    results: Vec<OwnerResult>,
+}
+
+struct OwnerResult {
+    query: String,
+    /// The owners (none means unowned), or why the path couldn't be looked up
+    owners: Result<Vec<PkgId>, String>,
+}
+
+impl FileOwnersState {
+    /// Look up the owners of `path` as soon as the index is ready
+    pub fn push_query(&mut self, path: String) {
+        self.pending.push(path);
+    }
+    pub fn on_pac_reload(&mut self) {
+        self.index = None;
+    }
+    /// Answer the pending queries if the index is ready. Returns whether it is.
+    fn process_pending(&mut self, ui_state: &mut SharedUiState) -> bool {
+        if self.pending.is_empty() && self.index.is_none() {
+            return false;
+        }
+        let job = self
+            .index
+            .get_or_insert_with(|| Job::spawn(FileIndex::load_local));
+        job.poll();
+        let index = match job.result() {
+            Some(Ok(index)) => index,
+            Some(Err(e)) => {
+                for query in self.pending.drain(..) {
+                    self.results.insert(
+                        0,
+                        OwnerResult {
+                            query,
+                            owners: Err(format!("Failed to index files: {e:#}")),
+                        },
+                    );
+                }
+                return false;
+            }
+            None => return false,
+        };
+        for query in self.pending.drain(..) {
+            let owners = owner_query_path(&query)
+                .map(|path| index.owners(&path).into_iter().cloned().collect::<Vec<_>>());
+            // A single owner is most likely what the user is after
+            if let Ok([owner]) = owners.as_deref() {
+                ui_state.cmd.push(Cmd::OpenPkgTab(owner.clone()));
+            }
+            self.results.insert(0, OwnerResult { query, owners });
+        }
+        true
+    }
+}
+
+pub fn ui(ui: &mut egui::Ui, ui_state: &mut SharedUiState, state: &mut FileOwnersState) {
+    let ready = state.process_pending(ui_state);
+    egui::TopBottomPanel::top("file_owners_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            let re = ui.add(
+                egui::TextEdit::singleline(&mut state.query)
+                    .hint_text("🔍 Path or command name")
+                    .desired_width(400.0),
+            );
+            let enter = re.lost_focus() && ui.input(|inp| inp.key_pressed(egui::Key::Enter));
+            if (enter || ui.button("Find owner").clicked()) && !state.query.trim().is_empty() {
+                let query = std::mem::take(&mut state.query);
+                state.push_query(query);
+            }
+            if !state.pending.is_empty() || state.index.as_ref().is_some_and(Job::is_pending) {
+                ui.spinner();
+                ui.label("Indexing package files...");
+            } else if ready && let Some(Some(Ok(index))) = state.index.as_ref().map(Job::result) {
+                ui.label(format!("Files of {} packages indexed", index.n_pkgs()));
+            }
+        });
+        ui.label("You can also drop files onto the window");
+        ui.add_space(4.0);
+    });
+    egui::ScrollArea::vertical()
+        .auto_shrink(false)
+        .show(ui, |ui| {
+            for result in &state.results {
+                ui.horizontal_wrapped(|ui| {
+                    ui.strong(&result.query);
+                    match &result.owners {
+                        Ok(owners) if owners.is_empty() => {
+                            ui.colored_label(egui::Color32::YELLOW, "is not owned by any package");
+                        }
+                        Ok(owners) => {
+                            ui.label("is owned by");
+                            for owner in owners {
+                                if ui.link(owner.name.as_str()).clicked() {
+                                    ui_state.cmd.push(Cmd::OpenPkgTab(owner.clone()));
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            ui.colored_label(egui::Color32::RED, e);
+                        }
+                    }
+                });
+            }
+        });
+}
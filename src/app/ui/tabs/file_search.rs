@@ -0,0 +1,119 @@
+use {
+    super::remote_pkg_list::installed_label_for_remote_pkg,
+    crate::{
+        app::{
+            file_index::{FileMatch, FileQuery, SyncFileIndex},
+            ui::{PacState, SharedUiState, cmd::Cmd},
+        },
+        job::Job,
+        trans::TransRequest,
+    },
+    eframe::egui,
+    std::sync::Arc,
+};
+
+/// The index a search used, and the matches
+type SearchResult = anyhow::Result<(Arc<SyncFileIndex>, Vec<FileMatch>)>;
+
+/// Find the sync packages that ship a file, like `pacman -F`
+#[derive(Default)]
+pub struct FileSearchState {
+    query: String,
+    regex: bool,
+    /// Loaded by the first search, and reused by the following ones
+    index: Option<Arc<SyncFileIndex>>,
+    search: Option<Job<SearchResult>>,
+}
+
+impl FileSearchState {
+    pub fn on_pac_reload(&mut self) {
+        // Probably after synchronizing the files databases
+        self.index = None;
+    }
+}
+
+/// Search the file lists with `index`, loading it first if there is none yet
+fn search_files(index: Option<Arc<SyncFileIndex>>, query: &str, regex: bool) -> SearchResult {
+    let query = FileQuery::new(query, regex)?;
+    let index = match index {
+        Some(index) => index,
+        None => Arc::new(SyncFileIndex::load()?),
+    };
+    let matches = index.search(&query);
+    Ok((index, matches))
+}
+
+pub fn ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    state: &mut FileSearchState,
+) {
+    if let Some(job) = &mut state.search
+        && job.poll()
+        && let Some(Ok((index, _))) = job.result()
+    {
+        state.index = Some(Arc::clone(index));
+    }
+    let searching = state.search.as_ref().is_some_and(Job::is_pending);
+    egui::TopBottomPanel::top("file_search_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            let re = ui.add(
+                egui::TextEdit::singleline(&mut state.query)
+                    .hint_text("🔍 File name or path, e.g. bin/rg")
+                    .desired_width(300.0),
+            );
+            ui.toggle_value(&mut state.regex, ".*")
+                .on_hover_text("Match as a regex, like pacman -Fx");
+            let enter = re.lost_focus() && ui.input(|inp| inp.key_pressed(egui::Key::Enter));
+            let search = ui.add_enabled(!searching, egui::Button::new("Search"));
+            if (enter || search.clicked()) && !searching && !state.query.trim().is_empty() {
+                let (query, regex) = (state.query.clone(), state.regex);
+                let index = state.index.clone();
+                state.search = Some(Job::spawn(move || search_files(index, &query, regex)));
+            }
+            if searching {
+                ui.spinner();
+                if state.index.is_some() {
+                    ui.label("Searching the file lists...");
+                } else {
+                    ui.label("Reading the files databases...");
+                }
+            } else if let Some(Ok((_, matches))) = state.search.as_ref().and_then(Job::result) {
+                ui.label(format!("{} packages found", matches.len()));
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("🔁 Sync files databases (pacman -Fy)").clicked() {
+                    ui_state.cmd.push(Cmd::Transact(TransRequest::sync_files()));
+                }
+            });
+        });
+        ui.add_space(4.0);
+    });
+    let matches = match state.search.as_ref().and_then(Job::result) {
+        Some(Ok((_, matches))) => matches,
+        Some(Err(e)) => {
+            ui.colored_label(egui::Color32::RED, format!("{e:#}"));
+            return;
+        }
+        None => return,
+    };
+    egui::ScrollArea::vertical().auto_shrink(false).show_rows(
+        ui,
+        18.0,
+        matches.len(),
+        |ui, range| {
+            for m in &matches[range] {
+                ui.horizontal(|ui| {
+                    if ui.link(format!("{} {}", m.pkg, m.version)).clicked() {
+                        ui_state.cmd.push(Cmd::OpenPkgTab(m.pkg.clone()));
+                    }
+                    if let Some(pkg) = pac.find(&m.pkg) {
+                        installed_label_for_remote_pkg(ui, &mut ui_state.cmd, pkg, pac);
+                    }
+                    ui.label(m.files.join("  "));
+                });
+            }
+        },
+    );
+}
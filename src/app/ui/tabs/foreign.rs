@@ -0,0 +1,185 @@
+use {
+    super::pkg_table::format_date,
+    crate::{
+        alpm_util::PkgId,
+        app::{
+            pac_state::PkgInfo,
+            ui::{PacState, SharedUiState, cmd::Cmd},
+        },
+    },
+    alpm::PackageValidation,
+    eframe::egui,
+    egui_extras::{Column, TableBuilder},
+    smol_str::SmolStr,
+};
+
+/// A local package that didn't come from a sync db
+pub struct ForeignPkg {
+    pub name: SmolStr,
+    pub version: SmolStr,
+    pub build_date: i64,
+    pub packager: Option<String>,
+    /// The sync db package with the same name (only for unvalidated packages)
+    pub in_repo: Option<(PkgId, SmolStr)>,
+}
+
+impl ForeignPkg {
+    fn new(local: &PkgInfo, remote: Option<&PkgInfo>) -> Self {
+        Self {
+            name: local.name.clone(),
+            version: local.version.clone(),
+            build_date: local.build_date,
+            packager: local.packager.clone(),
+            in_repo: remote.map(|remote| (remote.id(), remote.version.clone())),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ForeignPkgsState {
+    /// Computed lazily from the package lists
+    pkgs: Option<Vec<ForeignPkg>>,
+    /// Also list the packages [`find_unvalidated`] finds
+    show_unvalidated: bool,
+    /// Computed lazily from the package lists
+    unvalidated: Option<Vec<ForeignPkg>>,
+}
+
+impl ForeignPkgsState {
+    pub fn pkgs(&mut self, pac: &PacState) -> &[ForeignPkg] {
+        self.pkgs.get_or_insert_with(|| find_foreign(pac))
+    }
+    pub fn on_pac_reload(&mut self) {
+        self.pkgs = None;
+        self.unvalidated = None;
+    }
+}
+
+/// Find the local packages that are in no sync db, like `pacman -Qm`
+pub fn find_foreign(pac: &PacState) -> Vec<ForeignPkg> {
+    pac.local_pkg_list
+        .iter()
+        .filter(|local| pac.find_remote(&local.name).is_none())
+        .map(|local| ForeignPkg::new(local, None))
+        .collect()
+}
+
+/// Find the local packages that have a namesake in a sync db, but were installed without
+/// validation, so they were probably built locally (e.g. with `makepkg -i`)
+pub fn find_unvalidated(pac: &PacState) -> Vec<ForeignPkg> {
+    pac.local_pkg_list
+        .iter()
+        .filter(|local| local.validation.contains(PackageValidation::NONE))
+        .filter_map(|local| {
+            let remote = pac.find_remote(&local.name)?;
+            Some(ForeignPkg::new(local, Some(remote)))
+        })
+        .collect()
+}
+
+pub fn ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    tab_state: &mut ForeignPkgsState,
+) {
+    let n_foreign = tab_state.pkgs(pac).len();
+    egui::TopBottomPanel::top("foreign_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "{n_foreign} packages that are in no sync db (pacman -Qm)"
+            ));
+            ui.separator();
+            ui.checkbox(
+                &mut tab_state.show_unvalidated,
+                "Also list locally built packages that are in a sync db",
+            );
+        });
+        ui.add_space(4.0);
+    });
+    if tab_state.show_unvalidated {
+        let unvalidated = tab_state
+            .unvalidated
+            .get_or_insert_with(|| find_unvalidated(pac));
+        egui::TopBottomPanel::bottom("foreign_unvalidated_panel")
+            .resizable(true)
+            .show_inside(ui, |ui| {
+                ui.add_space(4.0);
+                ui.heading(format!(
+                    "Installed without validation, but in a sync db ({})",
+                    unvalidated.len()
+                ));
+                ui.label(
+                    "These were probably built locally, e.g. with makepkg -i. \
+                     They are not foreign packages, as pacman -Qm doesn't list them.",
+                );
+                pkgs_table_ui(ui, "foreign_unvalidated_table", unvalidated, ui_state);
+            });
+    }
+    pkgs_table_ui(ui, "foreign_table", tab_state.pkgs(pac), ui_state);
+}
+
+fn pkgs_table_ui(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    pkgs: &[ForeignPkg],
+    ui_state: &mut SharedUiState,
+) {
+    let in_repo = pkgs.iter().any(|pkg| pkg.in_repo.is_some());
+    let mut table = TableBuilder::new(ui)
+        .id_salt(id_salt)
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::auto());
+    table = if in_repo {
+        table.column(Column::auto()).column(Column::remainder())
+    } else {
+        table.column(Column::remainder())
+    };
+    table
+        .auto_shrink(false)
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .header(18.0, |mut row| {
+            let labels = ["Name", "Installed version", "Build date", "Packager"];
+            for label in labels.into_iter().chain(in_repo.then_some("In a sync db")) {
+                row.col(|ui| {
+                    ui.label(label);
+                });
+            }
+        })
+        .body(|mut body| {
+            body.ui_mut().style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            body.rows(22.0, pkgs.len(), |mut row| {
+                let pkg = &pkgs[row.index()];
+                row.col(|ui| {
+                    if ui.link(pkg.name.as_str()).clicked() {
+                        ui_state.cmd.push(Cmd::OpenPkgTab(PkgId::local(&pkg.name)));
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(pkg.version.as_str());
+                });
+                row.col(|ui| {
+                    ui.label(format_date(pkg.build_date));
+                });
+                row.col(|ui| {
+                    ui.label(pkg.packager.as_deref().unwrap_or("-"));
+                });
+                if let Some((id, version)) = &pkg.in_repo {
+                    row.col(|ui| {
+                        if ui
+                            .link(
+                                egui::RichText::new(format!("{id} {version}"))
+                                    .color(egui::Color32::YELLOW),
+                            )
+                            .on_hover_text("The package with the same name in a sync db")
+                            .clicked()
+                        {
+                            ui_state.cmd.push(Cmd::OpenPkgTab(id.clone()));
+                        }
+                    });
+                }
+            });
+        });
+}
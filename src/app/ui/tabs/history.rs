@@ -0,0 +1,299 @@
+use {
+    super::pkg_table::format_date,
+    crate::{
+        app::{
+            history::{History, Outcome, PkgEvent, Rollback},
+            pkg_cache::{self, CachedPkg},
+            ui::{PacState, SharedUiState, cmd::Cmd},
+        },
+        job::Job,
+        trans::{PkgOpKind, TransRequest},
+    },
+    chrono::{Local, NaiveDate, TimeZone},
+    eframe::egui,
+};
+
+/// How many transactions are shown at first, and added by "Show more"
+const PAGE_SIZE: usize = 100;
+
+/// The package transactions recorded in pacman.log
+pub struct HistoryState {
+    history: Option<Job<anyhow::Result<History>>>,
+    pkg_filter: String,
+    /// Dates as `YYYY-MM-DD`, inclusive
+    from: String,
+    to: String,
+    /// How many of the newest matching transactions to show
+    limit: usize,
+    /// A rollback (of the transaction that started at this time) with notes, waiting to be
+    /// confirmed
+    pending_rollback: Option<(i64, Rollback)>,
+    /// The package cache scan that a rollback (of the transaction with this index) waits for
+    rollback_scan: Option<(usize, Job<anyhow::Result<Vec<CachedPkg>>>)>,
+    /// Why the last rollback couldn't be worked out
+    rollback_error: Option<String>,
+}
+
+impl Default for HistoryState {
+    fn default() -> Self {
+        Self {
+            history: None,
+            pkg_filter: String::new(),
+            from: String::new(),
+            to: String::new(),
+            limit: PAGE_SIZE,
+            pending_rollback: None,
+            rollback_scan: None,
+            rollback_error: None,
+        }
+    }
+}
+
+impl HistoryState {
+    pub fn on_pac_reload(&mut self) {
+        // There's probably a new transaction in the log
+        self.history = None;
+        // Which would shift the transaction indices
+        self.rollback_scan = None;
+    }
+}
+
+/// Parse a date range bound. Empty means unbounded, `Err` means invalid.
+fn parse_bound(date: &str, end: bool) -> Result<Option<i64>, ()> {
+    let date = date.trim();
+    if date.is_empty() {
+        return Ok(None);
+    }
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| ())?;
+    let time = if end {
+        date.and_hms_opt(23, 59, 59)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    }
+    .ok_or(())?;
+    let time = Local.from_local_datetime(&time).earliest().ok_or(())?;
+    Ok(Some(time.timestamp()))
+}
+
+pub fn ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    state: &mut HistoryState,
+) {
+    let job = state
+        .history
+        .get_or_insert_with(|| Job::spawn(History::load));
+    job.poll();
+    let history = match job.result() {
+        Some(Ok(history)) => history,
+        Some(Err(e)) => {
+            ui.colored_label(egui::Color32::RED, format!("Failed to load history: {e:#}"));
+            return;
+        }
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Reading pacman.log...");
+            });
+            return;
+        }
+    };
+    if let Some((idx, job)) = &mut state.rollback_scan
+        && job.poll()
+    {
+        let trans = &history.transactions[*idx];
+        match job.take_result() {
+            Some(Ok(cache)) => {
+                let rollback = history.rollback(*idx, pac, &cache);
+                if rollback.notes.is_empty() && !rollback.ops.is_empty() {
+                    ui_state.cmd.push(Cmd::Transact(TransRequest {
+                        ops: rollback.ops,
+                        set_reasons: rollback.set_reasons,
+                        ..Default::default()
+                    }));
+                } else {
+                    state.pending_rollback = Some((trans.time, rollback));
+                }
+            }
+            Some(Err(e)) => {
+                eprintln!("Failed to scan the package cache: {e}");
+                state.rollback_error = Some(format!("Failed to scan the package cache: {e:#}"));
+            }
+            None => {
+                state.rollback_error =
+                    Some("Scanning the package cache stopped without a result".into());
+            }
+        }
+        state.rollback_scan = None;
+    }
+    let mut from = Ok(None);
+    let mut to = Ok(None);
+    egui::TopBottomPanel::top("history_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut state.pkg_filter)
+                    .hint_text("🔍 Package")
+                    .desired_width(200.0),
+            );
+            for (label, date, bound, end) in [
+                ("From", &mut state.from, &mut from, false),
+                ("To", &mut state.to, &mut to, true),
+            ] {
+                ui.label(label);
+                *bound = parse_bound(date, end);
+                let mut edit = egui::TextEdit::singleline(date)
+                    .hint_text("YYYY-MM-DD")
+                    .desired_width(90.0);
+                if bound.is_err() {
+                    edit = edit.text_color(egui::Color32::RED);
+                }
+                ui.add(edit);
+            }
+            ui.separator();
+            ui.label(format!("{} transactions", history.transactions.len()));
+        });
+        if state.rollback_scan.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Scanning the package cache for the old versions...");
+            });
+        }
+        if let Some(e) = &state.rollback_error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+        if let Some((time, rollback)) = &state.pending_rollback {
+            let mut close = false;
+            ui.separator();
+            ui.strong(format!(
+                "Rolling back the transaction of {}",
+                format_date(*time)
+            ));
+            for note in &rollback.notes {
+                ui.colored_label(egui::Color32::YELLOW, note);
+            }
+            ui.horizontal(|ui| {
+                if rollback.ops.is_empty() {
+                    ui.label("There is nothing to roll back");
+                } else if ui.button("Continue").clicked() {
+                    ui_state.cmd.push(Cmd::Transact(TransRequest {
+                        ops: rollback.ops.clone(),
+                        set_reasons: rollback.set_reasons.clone(),
+                        ..Default::default()
+                    }));
+                    close = true;
+                }
+                close |= ui.button("Cancel").clicked();
+            });
+            if close {
+                state.pending_rollback = None;
+            }
+        }
+        ui.add_space(4.0);
+    });
+    let (Ok(from), Ok(to)) = (from, to) else {
+        ui.label("Invalid date");
+        return;
+    };
+    let filt = state.pkg_filter.trim().to_ascii_lowercase();
+    let event_matches = |ev: &PkgEvent| filt.is_empty() || ev.op.name.contains(filt.as_str());
+    let matching = history.transactions.iter().enumerate().rev();
+    let matching = matching.filter(|(_, trans)| {
+        from.is_none_or(|from| trans.time >= from)
+            && to.is_none_or(|to| trans.time <= to)
+            && trans.events.iter().any(event_matches)
+    });
+    egui::ScrollArea::vertical()
+        .auto_shrink(false)
+        .show(ui, |ui| {
+            let mut shown = 0;
+            for (i, (idx, trans)) in matching.enumerate() {
+                if i == state.limit {
+                    if ui.button("Show more").clicked() {
+                        state.limit += PAGE_SIZE;
+                    }
+                    break;
+                }
+                shown += 1;
+                let outcome = match trans.outcome {
+                    Outcome::Completed | Outcome::Unknown => "",
+                    Outcome::Failed => " ✘ failed",
+                    Outcome::Interrupted => " ✘ interrupted",
+                };
+                let title = format!(
+                    "{}  {}  ({} packages){outcome}",
+                    format_date(trans.time),
+                    trans.command.as_deref().unwrap_or("<unknown command>"),
+                    trans.events.len()
+                );
+                egui::CollapsingHeader::new(title)
+                    .id_salt(("history_trans", trans.time, i))
+                    .default_open(!filt.is_empty())
+                    .show(ui, |ui| {
+                        if trans.outcome != Outcome::Failed
+                            && ui
+                                .add_enabled(
+                                    state.rollback_scan.is_none(),
+                                    egui::Button::new("↶ Roll back"),
+                                )
+                                .on_hover_text(
+                                    "Remove what this installed, and go back to the old \
+                                     versions of what it upgraded, downgraded or removed",
+                                )
+                                .clicked()
+                        {
+                            state.pending_rollback = None;
+                            state.rollback_error = None;
+                            state.rollback_scan = Some((idx, Job::spawn(pkg_cache::scan)));
+                        }
+                        egui::Grid::new(("history_events", trans.time, i)).show(ui, |ui| {
+                            for ev in trans.events.iter().filter(|ev| event_matches(ev)) {
+                                event_ui(ui, pac, ui_state, ev, true);
+                                ui.end_row();
+                            }
+                        });
+                    });
+            }
+            if shown == 0 {
+                ui.label("No matching transactions");
+            }
+        });
+}
+
+/// One grid row for a logged package operation
+pub fn event_ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    ev: &PkgEvent,
+    show_name: bool,
+) {
+    ui.label(format_date(ev.time));
+    let color = match ev.op.kind {
+        PkgOpKind::Install => egui::Color32::GREEN,
+        PkgOpKind::Upgrade => ui.visuals().text_color(),
+        PkgOpKind::Reinstall => ui.visuals().weak_text_color(),
+        PkgOpKind::Downgrade => egui::Color32::YELLOW,
+        PkgOpKind::Remove => egui::Color32::RED,
+    };
+    ui.colored_label(color, ev.op.kind.label());
+    if show_name {
+        let name = ev.op.name.as_str();
+        match pac.find_local(name).or_else(|| pac.find_remote(name)) {
+            Some(pkg) => {
+                if ui.link(name).clicked() {
+                    ui_state.cmd.push(Cmd::OpenPkgTab(pkg.id()));
+                }
+            }
+            None => {
+                ui.label(name).on_hover_text("No longer available");
+            }
+        }
+    }
+    let versions = match (&ev.op.old_ver, &ev.op.new_ver) {
+        (Some(old), Some(new)) if old != new => format!("{old} → {new}"),
+        (Some(ver), _) | (None, Some(ver)) => ver.to_string(),
+        (None, None) => String::new(),
+    };
+    ui.label(versions);
+}
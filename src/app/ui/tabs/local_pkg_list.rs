@@ -1,94 +1,108 @@
 use {
-    super::PkgListState,
+    super::{PkgListState, ReasonView},
     crate::{
-        alpm_util::PkgId,
-        app::ui::{PacState, SharedUiState, cmd::Cmd},
+        app::{
+            pac_state::PkgInfo,
+            ui::{PacState, SharedUiState, cmd::Cmd},
+        },
+        trans::{Reason, TransOp, TransRequest},
     },
     eframe::egui,
-    egui_extras::{Column, TableBuilder},
+    smol_str::SmolStr,
+    std::sync::Arc,
 };
 
 pub fn ui(
     ui: &mut egui::Ui,
-    pac: &mut PacState,
+    pac: &Arc<PacState>,
     ui_state: &mut SharedUiState,
     tab_state: &mut PkgListState,
 ) {
-    egui::TopBottomPanel::top("top_panel").show_inside(ui, |ui| {
-        ui.horizontal(|ui| {
-            pac.with_mut(|this| {
-                if ui
-                    .add(
-                        egui::TextEdit::singleline(&mut tab_state.filter_string)
-                            .hint_text("🔍 Filter"),
-                    )
-                    .changed()
-                {
-                    *this.filt_local_pkg_list = this
-                        .local_pkg_list
-                        .iter()
-                        .filter(|pkg| {
-                            let filt_lo = tab_state.filter_string.to_ascii_lowercase();
-                            pkg.name().contains(&filt_lo)
-                                || pkg.desc().is_some_and(|desc| {
-                                    desc.to_ascii_lowercase().contains(&filt_lo)
-                                })
-                                || pkg
-                                    .provides()
-                                    .iter()
-                                    .any(|dep| dep.name().contains(&filt_lo))
-                        })
-                        .copied()
-                        .collect();
+    tab_state.ui(ui, pac, ui_state, list, true);
+}
+
+pub fn list(pac: &PacState) -> &[PkgInfo] {
+    &pac.local_pkg_list
+}
+
+/// Install reason views, and actions for the selected packages.
+///
+/// Returns whether the view was changed.
+pub fn reason_bar_ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    tab_state: &mut PkgListState,
+) -> bool {
+    let mut view_changed = false;
+    ui.horizontal(|ui| {
+        let n_orphans = pac.local_orphans().count();
+        for (view, label) in [
+            (ReasonView::All, "All".to_string()),
+            (ReasonView::Explicit, "Explicit".to_string()),
+            (ReasonView::Depend, "Dependencies".to_string()),
+            (ReasonView::Orphans, format!("Orphans ({n_orphans})")),
+        ] {
+            view_changed |= ui
+                .selectable_value(&mut tab_state.reason_view, view, label)
+                .changed();
+        }
+        ui.separator();
+        let selected = &mut tab_state.selected;
+        // The actions only apply to the selected packages that are listed, not to ones selected
+        // before the filter or view changed
+        let listed: Vec<SmolStr> = tab_state
+            .filtered
+            .iter()
+            .map(|&i| &pac.local_pkg_list[i].name)
+            .filter(|name| selected.contains(*name))
+            .cloned()
+            .collect();
+        ui.label(format!("{} selected", listed.len()));
+        if listed.len() != selected.len() {
+            ui.weak(format!("({} not listed)", selected.len() - listed.len()));
+        }
+        ui.add_enabled_ui(!listed.is_empty(), |ui| {
+            if ui.button("🗑 Remove").clicked() {
+                ui_state.cmd.push(Cmd::Transact(TransRequest {
+                    ops: listed.iter().cloned().map(TransOp::Remove).collect(),
+                    ..Default::default()
+                }));
+            }
+            for (reason, label) in [
+                (Reason::Explicit, "Mark as explicit"),
+                (Reason::Depend, "Mark as dependency"),
+            ] {
+                if ui.button(label).clicked() {
+                    ui_state.cmd.push(Cmd::Transact(TransRequest::set_reasons(
+                        listed.iter().cloned(),
+                        reason,
+                    )));
                 }
-                ui.spacing();
-                ui.label(format!(
-                    "{} packages listed",
-                    this.filt_local_pkg_list.len()
-                ));
-            });
+            }
+            if ui.button("🩺 Verify").clicked() {
+                let mut pkgs = listed.clone();
+                pkgs.sort();
+                ui_state.cmd.push(Cmd::Verify(pkgs));
+            }
         });
-        ui.add_space(4.0);
+        if ui.button("Select listed").clicked() {
+            selected.extend(
+                tab_state
+                    .filtered
+                    .iter()
+                    .map(|&i| pac.local_pkg_list[i].name.clone()),
+            );
+        }
+        if ui.button("Select none").clicked() {
+            selected.clear();
+        }
     });
-    pkg_list_table_builder(ui)
-        .header(18.0, |mut row| {
-            row.col(|ui| {
-                ui.label("Name");
-            });
-            row.col(|ui| {
-                ui.label("Version");
-            });
-            row.col(|ui| {
-                ui.label("Description");
-            });
-        })
-        .body(|mut body| {
-            body.ui_mut().style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-            pac.with_filt_local_pkg_list(|list| {
-                body.rows(22.0, list.len(), |mut row| {
-                    let pkg = &list[row.index()];
-                    row.col(|ui| {
-                        if ui.link(pkg.name()).clicked() {
-                            ui_state.cmd.push(Cmd::OpenPkgTab(PkgId::local(pkg.name())));
-                        }
-                    });
-                    row.col(|ui| {
-                        ui.label(pkg.version().to_string());
-                    });
-                    row.col(|ui| {
-                        ui.label(pkg.desc().unwrap_or("<missing description>"));
-                    });
-                });
-            });
-        });
-}
-
-pub fn pkg_list_table_builder(ui: &mut egui::Ui) -> TableBuilder {
-    TableBuilder::new(ui)
-        .column(Column::auto())
-        .column(Column::auto())
-        .column(Column::remainder())
-        .auto_shrink(false)
-        .striped(true)
-        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+    if tab_state.reason_view == ReasonView::Orphans {
+        ui.label(
+            "Packages installed as dependencies that no explicitly installed package needs. \
+             Unlike pacman -Qdt, this includes orphans that only require each other.",
+        );
+    }
+    view_changed
 }
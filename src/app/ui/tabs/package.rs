@@ -1,19 +1,42 @@
 use {
-    super::remote_pkg_list::installed_label_for_remote_pkg,
+    super::{history, pkg_table::format_date, remote_pkg_list::installed_label_for_remote_pkg},
     crate::{
-        alpm_util::{PkgId, deduped_files},
-        app::ui::{PacState, SharedUiState, cmd::Cmd},
+        alpm_util::{PkgId, Unsatisfied},
+        app::{
+            config_files::{BackupFile, BackupState, backup_files},
+            file_tree::{FileKind, FileNode, FileTree, mode_string},
+            history::History,
+            pac_state::{DepInfo, PkgInfo, WhyLink},
+            pkg_cache::{self, CachedVersion},
+            ui::{PacState, SharedUiState, cmd::Cmd},
+        },
+        job::Job,
+        trans::{TransOp, TransRequest},
     },
-    eframe::egui,
-    humansize::format_size_i,
-    std::process::Command,
+    alpm::PackageReason,
+    eframe::egui::{self, collapsing_header::CollapsingState},
+    humansize::{format_size, format_size_i},
+    std::{cmp::Ordering, path::Path, process::Command},
 };
 
 pub struct PkgTab {
     pub id: PkgId,
     tab: PkgTabTab,
     pub force_close: bool,
+    /// The package couldn't be found last time we looked (e.g. it was removed)
+    pub missing: bool,
     files_filt_string: String,
+    /// Loaded when the file list is first shown
+    files: Option<Job<anyhow::Result<FileTree>>>,
+    /// Bumped by "expand/collapse all" to reset the open state of every directory
+    files_open_gen: u32,
+    files_default_open: bool,
+    /// Loaded when the general tab of a local package is first shown
+    backup_files: Option<Job<anyhow::Result<Vec<BackupFile>>>>,
+    /// Loaded when the history is first shown
+    history: Option<Job<anyhow::Result<History>>>,
+    /// Loaded when the cached versions of a local package are first shown
+    versions: Option<Job<anyhow::Result<Vec<CachedVersion>>>>,
 }
 
 impl PkgTab {
@@ -22,9 +45,23 @@ impl PkgTab {
             id,
             tab: PkgTabTab::default(),
             force_close: false,
+            missing: false,
             files_filt_string: String::new(),
+            files: None,
+            files_open_gen: 0,
+            files_default_open: false,
+            backup_files: None,
+            history: None,
+            versions: None,
         }
     }
+    pub fn on_pac_reload(&mut self, pac: &PacState) {
+        self.missing = !pkg_exists(pac, &self.id);
+        self.files = None;
+        self.backup_files = None;
+        self.history = None;
+        self.versions = None;
+    }
 }
 
 #[derive(PartialEq, Default)]
@@ -32,6 +69,12 @@ enum PkgTabTab {
     #[default]
     General,
     Files,
+    History,
+    Versions,
+}
+
+pub fn pkg_exists(pac: &PacState, id: &PkgId) -> bool {
+    pac.find(id).is_some()
 }
 
 pub fn ui(ui: &mut egui::Ui, pac: &PacState, ui_state: &mut SharedUiState, pkg_tab: &mut PkgTab) {
@@ -43,162 +86,630 @@ pub fn ui(ui: &mut egui::Ui, pac: &PacState, ui_state: &mut SharedUiState, pkg_t
         pkg_tab.force_close = true;
     }
     let remote = pkg_tab.id.is_remote();
-    pac.with(|this| {
-        let pkg_list = if remote {
-            this.remote_pkg_list
-        } else {
-            this.local_pkg_list
-        };
-        match pkg_list.iter().find(|pkg| pkg_tab.id.matches_pkg(pkg)) {
-            Some(pkg) => {
-                ui.horizontal(|ui| {
-                    if let Some(db) = pkg.db() {
-                        ui.label(format!("{}/", db.name()));
-                    }
-                    ui.heading(pkg.name());
-                    ui.label(pkg.version().to_string());
-                    if remote {
-                        installed_label_for_remote_pkg(ui, ui_state, pkg, this.local_pkg_list);
+    let resolved = pac.find(&pkg_tab.id);
+    pkg_tab.missing = resolved.is_none();
+    match resolved {
+        Some(pkg) => {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}/", pkg.db));
+                ui.heading(pkg.name.as_str());
+                ui.label(pkg.version.as_str());
+                if remote {
+                    installed_label_for_remote_pkg(ui, &mut ui_state.cmd, pkg, pac);
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let op = if remote {
+                        let reinstall = pac
+                            .find_local(&pkg.name)
+                            .is_some_and(|local| local.version == pkg.version);
+                        let label = if reinstall {
+                            "⟳ Reinstall"
+                        } else {
+                            "⬇ Install"
+                        };
+                        ui.button(label)
+                            .clicked()
+                            .then(|| TransOp::Install(pkg_tab.id.clone()))
+                    } else {
+                        let in_sync = pac.find_remote(&pkg.name).is_some();
+                        let remove = ui
+                            .button("🗑 Remove")
+                            .clicked()
+                            .then(|| TransOp::Remove(pkg.name.clone()));
+                        let reinstall = (in_sync && ui.button("⟳ Reinstall").clicked())
+                            .then(|| TransOp::Reinstall(pkg.name.clone()));
+                        if ui
+                            .button("🩺 Verify")
+                            .on_hover_text("Check the installed files (pacman -Qkk)")
+                            .clicked()
+                        {
+                            ui_state.cmd.push(Cmd::Verify(vec![pkg.name.clone()]));
+                        }
+                        remove.or(reinstall)
+                    };
+                    if let Some(op) = op {
+                        ui_state.cmd.push(Cmd::Transact(TransRequest::single(op)));
                     }
                 });
-                ui.separator();
-                ui.horizontal(|ui| {
-                    ui.selectable_value(&mut pkg_tab.tab, PkgTabTab::General, "General");
-                    ui.selectable_value(&mut pkg_tab.tab, PkgTabTab::Files, "File list");
-                });
-                ui.separator();
-                match pkg_tab.tab {
-                    PkgTabTab::General => {
-                        ui.label(pkg.desc().unwrap_or("<no description>"));
-                        if let Some(url) = pkg.url() {
-                            ui.horizontal(|ui| {
-                                ui.label("URL");
-                                ui.hyperlink(url);
-                            });
-                        }
-                        ui.label(format!(
-                            "Installed size: {}",
-                            format_size_i(pkg.isize(), humansize::BINARY)
-                        ));
-                        let deps = pkg.depends();
-                        ui.heading(format!("Dependencies ({})", deps.len()));
-                        if deps.is_empty() {
-                            ui.label("<none>");
-                        } else {
-                            ui.horizontal_wrapped(|ui| {
-                                for dep in deps {
-                                    let resolved = pkg_list.iter().find(|pkg| {
-                                        pkg.name() == dep.name()
-                                            || pkg.provides().iter().any(|dep2| {
-                                                // TODO: This might not be correct/enough
-                                                dep2.name() == dep.name()
-                                                    && dep2.version() >= dep.version()
-                                            })
-                                    });
-                                    match resolved {
-                                        Some(pkg) => {
-                                            let label = if dep.name() == pkg.name() {
-                                                dep.name()
-                                            } else {
-                                                &format!("{} ({})", dep.name(), pkg.name())
-                                            };
-                                            if ui.link(label).clicked() {
-                                                ui_state.cmd.push(Cmd::OpenPkgTab(
-                                                    PkgId::qualified(&pkg_tab.id.db, pkg.name()),
-                                                ));
-                                            }
-                                        }
-                                        None => {
-                                            ui.label(format!("{} (unresolved)", dep));
-                                        }
-                                    }
-                                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut pkg_tab.tab, PkgTabTab::General, "General");
+                ui.selectable_value(&mut pkg_tab.tab, PkgTabTab::Files, "File list");
+                ui.selectable_value(&mut pkg_tab.tab, PkgTabTab::History, "History");
+                if !remote {
+                    ui.selectable_value(&mut pkg_tab.tab, PkgTabTab::Versions, "Versions");
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("🕸 Dependency graph").clicked() {
+                        ui_state.cmd.push(Cmd::OpenDepGraph(pkg_tab.id.clone()));
+                    }
+                    for (label, reverse) in
+                        [("🌳 Dependents tree", true), ("🌳 Dependency tree", false)]
+                    {
+                        if ui.button(label).clicked() {
+                            ui_state.cmd.push(Cmd::OpenDepTree {
+                                id: pkg_tab.id.clone(),
+                                reverse,
                             });
                         }
-                        let deps = pkg.optdepends();
-                        ui.heading(format!("Optional dependencies ({})", deps.len()));
-                        if deps.is_empty() {
-                            ui.label("<none>");
-                        } else {
+                    }
+                });
+            });
+            ui.separator();
+            match pkg_tab.tab {
+                PkgTabTab::General => {
+                    ui.label(pkg.desc.as_deref().unwrap_or("<no description>"));
+                    if let Some(url) = &pkg.url {
+                        ui.horizontal(|ui| {
+                            ui.label("URL");
+                            ui.hyperlink(url);
+                        });
+                    }
+                    ui.label(format!(
+                        "Installed size: {}",
+                        format_size_i(pkg.isize, humansize::BINARY)
+                    ));
+                    if !remote {
+                        why_installed_ui(ui, pac, ui_state, pkg);
+                    }
+                    let deps = &pkg.depends;
+                    ui.heading(format!("Dependencies ({})", deps.len()));
+                    if deps.is_empty() {
+                        ui.label("<none>");
+                    } else {
+                        ui.horizontal_wrapped(|ui| {
                             for dep in deps {
-                                ui.horizontal(|ui| {
-                                    if ui.link(dep.name()).clicked() {
-                                        ui_state.cmd.push(Cmd::OpenPkgTab(PkgId::qualified(
-                                            &pkg_tab.id.db,
-                                            dep.name(),
-                                        )));
-
-                                        if let Some(ver) = dep.version() {
-                                            ui.label(format!("={ver}"));
-                                        }
-                                    }
-                                    if let Some(desc) = dep.desc() {
-                                        ui.label(desc);
-                                    }
-                                });
+                                dep_ui(ui, pac, ui_state, dep, remote);
                             }
-                        }
-                        let reqs = pkg.required_by();
-                        ui.heading(format!("Required by ({})", reqs.len()));
-                        if reqs.is_empty() {
-                            ui.label("<none>");
-                        } else {
-                            ui.horizontal_wrapped(|ui| {
-                                for req in reqs {
-                                    if ui.link(&req).clicked() {
-                                        ui_state.cmd.push(Cmd::OpenPkgTab(PkgId::qualified(
-                                            &pkg_tab.id.db,
-                                            &req,
-                                        )));
-                                    }
-                                }
-                            });
-                        }
-                        let opt_for = pkg.optional_for();
-                        ui.heading(format!("Optional for ({})", opt_for.len()));
-                        if opt_for.is_empty() {
-                            ui.label("<none>");
-                        } else {
-                            ui.horizontal_wrapped(|ui| {
-                                for name in opt_for {
-                                    if ui.link(&name).clicked() {
-                                        ui_state.cmd.push(Cmd::OpenPkgTab(PkgId::qualified(
-                                            &pkg_tab.id.db,
-                                            &name,
-                                        )));
-                                    }
+                        });
+                    }
+                    let deps = &pkg.optdepends;
+                    ui.heading(format!("Optional dependencies ({})", deps.len()));
+                    if deps.is_empty() {
+                        ui.label("<none>");
+                    } else {
+                        for dep in deps {
+                            ui.horizontal(|ui| {
+                                dep_ui(ui, pac, ui_state, dep, remote);
+                                if let Some(desc) = &dep.desc {
+                                    ui.label(desc);
                                 }
                             });
                         }
-                        let provides = pkg.provides();
-                        ui.heading(format!("Provides ({})", provides.len()));
-                        for dep in provides {
-                            ui.label(dep.to_string());
-                        }
                     }
-                    PkgTabTab::Files => {
-                        ui.add(
-                            egui::TextEdit::singleline(&mut pkg_tab.files_filt_string)
-                                .hint_text("🔍 Filter"),
-                        );
-                        let files = pkg.files();
-                        let deduped_files = deduped_files(files.files()).filter(|file| {
-                            file.name()
-                                .to_ascii_lowercase()
-                                .contains(&pkg_tab.files_filt_string.to_ascii_lowercase())
+                    let reqs = pac.dependents(pkg, false);
+                    ui.heading(format!("Required by ({})", reqs.len()));
+                    if reqs.is_empty() {
+                        ui.label("<none>");
+                    } else {
+                        ui.horizontal_wrapped(|ui| {
+                            for req in reqs {
+                                if ui.link(req.name.as_str()).clicked() {
+                                    ui_state.cmd.push(Cmd::OpenPkgTab(req.id()));
+                                }
+                            }
                         });
-                        for file in deduped_files {
-                            let name = format!("/{}", file.name());
-                            if ui.link(&name).clicked() {
-                                Command::new("xdg-open").arg(name).status().unwrap();
+                    }
+                    let opt_for = pac.dependents(pkg, true);
+                    ui.heading(format!("Optional for ({})", opt_for.len()));
+                    if opt_for.is_empty() {
+                        ui.label("<none>");
+                    } else {
+                        ui.horizontal_wrapped(|ui| {
+                            for dependent in opt_for {
+                                if ui.link(dependent.name.as_str()).clicked() {
+                                    ui_state.cmd.push(Cmd::OpenPkgTab(dependent.id()));
+                                }
                             }
+                        });
+                    }
+                    let provides = &pkg.provides;
+                    ui.heading(format!("Provides ({})", provides.len()));
+                    for dep in provides {
+                        ui.label(dep.to_string());
+                    }
+                    if !remote {
+                        backup_files_ui(ui, ui_state, pkg_tab);
+                    }
+                }
+                PkgTabTab::Files => files_ui(ui, pkg_tab),
+                PkgTabTab::History => history_ui(ui, pac, ui_state, pkg_tab),
+                PkgTabTab::Versions => versions_ui(ui, pac, ui_state, pkg_tab, pkg),
+            }
+        }
+        None => {
+            ui.label("<Unresolved package>");
+        }
+    }
+}
+
+/// A dependency, linking to the package satisfying it.
+///
+/// Dependencies of local packages that aren't installed are looked up in the sync dbs.
+fn dep_ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    dep: &DepInfo,
+    remote: bool,
+) {
+    let spec = dep.spec();
+    match pac.resolve(dep, remote) {
+        Ok(pkg) => {
+            let label = if dep.name == pkg.name {
+                spec.to_string()
+            } else {
+                format!("{spec} ({})", pkg.name)
+            };
+            if ui.link(label).clicked() {
+                ui_state.cmd.push(Cmd::OpenPkgTab(pkg.id()));
+            }
+            providers_ui(ui, pac, ui_state, dep);
+        }
+        Err(e) => {
+            let available = if remote {
+                None
+            } else {
+                pac.resolve(dep, true).ok()
+            };
+            match available {
+                Some(pkg) => {
+                    let why = match e {
+                        Unsatisfied::NotFound => "not installed",
+                        Unsatisfied::WrongVersion(_) => "wrong version installed",
+                    };
+                    if ui
+                        .link(format!("{spec} ({why}, available in {})", pkg.db))
+                        .on_hover_text(e.to_string())
+                        .clicked()
+                    {
+                        ui_state.cmd.push(Cmd::OpenPkgTab(pkg.id()));
+                    }
+                }
+                None => {
+                    ui.colored_label(egui::Color32::RED, format!("{spec} (unsatisfiable)"))
+                        .on_hover_text(e.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// List the candidates for dependencies several packages can satisfy, like `sh` or
+/// `java-runtime`, highlighting the installed one
+fn providers_ui(ui: &mut egui::Ui, pac: &PacState, ui_state: &mut SharedUiState, dep: &DepInfo) {
+    let providers = pac.providers(dep);
+    if providers.len() < 2 {
+        return;
+    }
+    let installed = pac.resolve(dep, false).ok();
+    ui.menu_button(format!("{} providers", providers.len()), |ui| {
+        for provider in providers {
+            ui.horizontal(|ui| {
+                if ui
+                    .link(format!("{}/{}", provider.db, provider.name))
+                    .clicked()
+                {
+                    ui.close_menu();
+                    ui_state.cmd.push(Cmd::OpenPkgTab(provider.id()));
+                }
+                if installed.is_some_and(|local| local.name == provider.name) {
+                    ui.colored_label(egui::Color32::GREEN, "✔ installed");
+                }
+            });
+        }
+    });
+}
+
+/// How many chains to show before collapsing the rest
+const WHY_CHAINS_SHOWN: usize = 8;
+
+fn why_installed_ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    pkg: &PkgInfo,
+) {
+    ui.heading("Why is this installed?");
+    if pkg.reason == PackageReason::Explicit {
+        ui.label("It was explicitly installed");
+        return;
+    }
+    let chain_ui = |ui: &mut egui::Ui, ui_state: &mut SharedUiState, chain: &[WhyLink]| {
+        ui.horizontal_wrapped(|ui| {
+            for (i, link) in chain.iter().enumerate() {
+                if i > 0 {
+                    if link.optional {
+                        ui.label("← optional for");
+                    } else {
+                        ui.label("←");
+                    }
+                }
+                if ui.link(link.pkg.name.as_str()).clicked() {
+                    ui_state.cmd.push(Cmd::OpenPkgTab(link.pkg.id()));
+                }
+            }
+            ui.weak("(explicit)");
+        });
+    };
+    let chains_ui =
+        |ui: &mut egui::Ui, ui_state: &mut SharedUiState, chains: &[Vec<WhyLink>], salt: &str| {
+            for chain in chains.iter().take(WHY_CHAINS_SHOWN) {
+                chain_ui(ui, ui_state, chain);
+            }
+            if chains.len() > WHY_CHAINS_SHOWN {
+                egui::CollapsingHeader::new(format!("{} more", chains.len() - WHY_CHAINS_SHOWN))
+                    .id_salt(salt)
+                    .show(ui, |ui| {
+                        for chain in &chains[WHY_CHAINS_SHOWN..] {
+                            chain_ui(ui, ui_state, chain);
                         }
+                    });
+            }
+        };
+    let chains = pac.why_installed(pkg, false);
+    if chains.is_empty() {
+        ui.label(
+            "Installed as a dependency, but no explicitly installed package needs it (orphan)",
+        );
+    } else {
+        ui.label(format!(
+            "Needed by {} explicitly installed package(s), through:",
+            chains.len()
+        ));
+        chains_ui(ui, ui_state, &chains, "why_more");
+    }
+    let opt_chains = pac.why_installed(pkg, true);
+    if !opt_chains.is_empty() {
+        ui.label(format!(
+            "Optional for {} other explicitly installed package(s), through:",
+            opt_chains.len()
+        ));
+        chains_ui(ui, ui_state, &opt_chains, "why_more_optional");
+    }
+}
+
+/// The backup files of a local package, and whether they were modified since installation
+fn backup_files_ui(ui: &mut egui::Ui, ui_state: &mut SharedUiState, pkg_tab: &mut PkgTab) {
+    let job = pkg_tab.backup_files.get_or_insert_with(|| {
+        let name = pkg_tab.id.name.clone();
+        Job::spawn(move || backup_files(&name))
+    });
+    job.poll();
+    let files = match job.result() {
+        Some(Ok(files)) if files.is_empty() => return,
+        Some(Ok(files)) => files,
+        Some(Err(e)) => {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Failed to check backup files: {e:#}"),
+            );
+            return;
+        }
+        None => return,
+    };
+    ui.heading(format!("Backup files ({})", files.len()));
+    egui::Grid::new("backup_files").show(ui, |ui| {
+        for file in files {
+            ui.label(&file.path);
+            match &file.state {
+                BackupState::Unmodified => {
+                    ui.weak("unmodified");
+                }
+                BackupState::Modified => {
+                    ui.colored_label(egui::Color32::YELLOW, "modified");
+                }
+                BackupState::Missing => {
+                    ui.colored_label(egui::Color32::RED, "missing");
+                }
+                BackupState::Unreadable(e) => {
+                    ui.weak("can't be read").on_hover_text(e);
+                }
+            }
+            if file.pacnew {
+                if ui
+                    .link(".pacnew")
+                    .on_hover_text("A newer packaged version is waiting to be merged")
+                    .clicked()
+                {
+                    ui_state.cmd.push(Cmd::OpenConfigFiles);
+                }
+            } else {
+                ui.label("");
+            }
+            ui.end_row();
+        }
+    });
+}
+
+/// What pacman.log says happened to this package, newest first
+fn history_ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    pkg_tab: &mut PkgTab,
+) {
+    let job = pkg_tab
+        .history
+        .get_or_insert_with(|| Job::spawn(History::load));
+    job.poll();
+    let history = match job.result() {
+        Some(Ok(history)) => history,
+        Some(Err(e)) => {
+            ui.colored_label(egui::Color32::RED, format!("Failed to load history: {e:#}"));
+            return;
+        }
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Reading pacman.log...");
+            });
+            return;
+        }
+    };
+    let events: Vec<_> = history.pkg_events(&pkg_tab.id.name).collect();
+    if events.is_empty() {
+        ui.label("No mentions in pacman.log");
+        return;
+    }
+    egui::Grid::new("pkg_history").show(ui, |ui| {
+        for ev in events.into_iter().rev() {
+            history::event_ui(ui, pac, ui_state, ev, false);
+            ui.end_row();
+        }
+    });
+}
+
+/// The archives of this package in the cache, to downgrade (or upgrade) to
+fn versions_ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    ui_state: &mut SharedUiState,
+    pkg_tab: &mut PkgTab,
+    pkg: &PkgInfo,
+) {
+    let job = pkg_tab.versions.get_or_insert_with(|| {
+        let name = pkg.name.clone();
+        Job::spawn(move || pkg_cache::versions(&name))
+    });
+    job.poll();
+    let versions = match job.result() {
+        Some(Ok(versions)) => versions,
+        Some(Err(e)) => {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Failed to scan the package cache: {e:#}"),
+            );
+            return;
+        }
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Scanning the package cache...");
+            });
+            return;
+        }
+    };
+    if versions.is_empty() {
+        ui.label("No archives of this package in the cache");
+        return;
+    }
+    egui::Grid::new("pkg_versions")
+        .striped(true)
+        .show(ui, |ui| {
+            for label in ["Version", "Build date", "Size", "", ""] {
+                ui.strong(label);
+            }
+            ui.end_row();
+            for version in versions.iter().rev() {
+                ui.label(version.version.as_str());
+                ui.label(
+                    version
+                        .build_date
+                        .map_or_else(|| "<unreadable>".into(), format_date),
+                );
+                ui.label(format_size(version.size, humansize::BINARY));
+                let path = version.path.to_string_lossy();
+                let label = match alpm::vercmp(version.version.as_str(), pkg.version.as_str()) {
+                    Ordering::Equal => {
+                        ui.label("Installed");
+                        ui.end_row();
+                        continue;
                     }
+                    Ordering::Less => "⬇ Downgrade",
+                    Ordering::Greater => "⬆ Upgrade",
+                };
+                if ui.button(label).on_hover_text(path.as_ref()).clicked() {
+                    ui_state
+                        .cmd
+                        .push(Cmd::Transact(TransRequest::single(TransOp::InstallFile(
+                            path.into_owned(),
+                        ))));
+                }
+                let broken = pkg_cache::broken_deps(pac, pkg, version);
+                if broken.is_empty() {
+                    ui.label("");
+                } else {
+                    let needs: Vec<_> = broken
+                        .iter()
+                        .map(|(dependent, dep)| format!("{} needs {}", dependent.name, dep.spec()))
+                        .collect();
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", needs.join(", ")))
+                        .on_hover_text("Installing this version breaks these dependencies");
                 }
+                ui.end_row();
             }
-            None => {
-                ui.label("<Unresolved package>");
+        });
+}
+
+fn files_ui(ui: &mut egui::Ui, pkg_tab: &mut PkgTab) {
+    let job = pkg_tab.files.get_or_insert_with(|| {
+        let id = pkg_tab.id.clone();
+        Job::spawn(move || FileTree::load(&id))
+    });
+    job.poll();
+    let tree = match job.result() {
+        Some(Ok(tree)) => tree,
+        Some(Err(e)) => {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Failed to load file list: {e:#}"),
+            );
+            return;
+        }
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Loading file list...");
+            });
+            return;
+        }
+    };
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut pkg_tab.files_filt_string).hint_text("🔍 Filter"));
+        if ui.button("⊞ Expand all").clicked() {
+            pkg_tab.files_open_gen += 1;
+            pkg_tab.files_default_open = true;
+        }
+        if ui.button("⊟ Collapse all").clicked() {
+            pkg_tab.files_open_gen += 1;
+            pkg_tab.files_default_open = false;
+        }
+        ui.separator();
+        ui.label(format!("{} files", tree.n_files));
+        if tree.installed {
+            ui.label(format_size_i(tree.root.size, humansize::BINARY));
+            let missing = count_missing(&tree.root);
+            if missing != 0 {
+                ui.colored_label(egui::Color32::RED, format!("{missing} missing on disk"));
             }
         }
     });
+    let filt = pkg_tab.files_filt_string.to_ascii_lowercase();
+    // Show everything that matches when filtering
+    let default_open = pkg_tab.files_default_open || !filt.is_empty();
+    let id = ui
+        .id()
+        .with(("files", pkg_tab.files_open_gen, filt.is_empty()));
+    for child in &tree.root.children {
+        file_node_ui(ui, child, id, default_open, &filt);
+    }
+}
+
+fn count_missing(node: &FileNode) -> usize {
+    usize::from(node.missing) + node.children.iter().map(count_missing).sum::<usize>()
+}
+
+fn file_node_matches(node: &FileNode, filt: &str) -> bool {
+    node.path.to_ascii_lowercase().contains(filt)
+        || node
+            .children
+            .iter()
+            .any(|child| file_node_matches(child, filt))
+}
+
+fn file_node_ui(ui: &mut egui::Ui, node: &FileNode, id: egui::Id, default_open: bool, filt: &str) {
+    if !filt.is_empty() && !file_node_matches(node, filt) {
+        return;
+    }
+    let id = id.with(&node.name);
+    let header = |ui: &mut egui::Ui| {
+        let icon = match node.meta.as_ref().map(|meta| &meta.kind) {
+            Some(FileKind::Link(_)) => "🔗",
+            _ if node.is_dir => "📁",
+            _ => "🗋",
+        };
+        let mut name = egui::RichText::new(format!("{icon} {}", node.name));
+        if node.missing {
+            name = name.color(egui::Color32::RED);
+        }
+        let path = format!("/{}", node.path);
+        let re = if node.is_dir {
+            ui.add(egui::Label::new(name).sense(egui::Sense::click()))
+        } else {
+            ui.link(name)
+        };
+        let re = if node.missing {
+            re.on_hover_text("Missing on disk")
+        } else {
+            re.on_hover_text(&path)
+        };
+        if re.clicked() && !node.is_dir {
+            xdg_open(Path::new(&path));
+        }
+        re.context_menu(|ui| {
+            if ui.button("Open").clicked() {
+                ui.close_menu();
+                xdg_open(Path::new(&path));
+            }
+            if ui.button("Open containing folder").clicked() {
+                ui.close_menu();
+                xdg_open(Path::new(&path).parent().unwrap_or(Path::new("/")));
+            }
+            if ui.button("Copy path").clicked() {
+                ui.close_menu();
+                ui.ctx().copy_text(path.clone());
+            }
+        });
+        if let Some(meta) = &node.meta {
+            ui.weak(egui::RichText::new(mode_string(meta.mode)).monospace());
+            if let FileKind::Link(target) = &meta.kind {
+                ui.weak(format!("→ {target}"));
+            } else if !matches!(meta.kind, FileKind::File | FileKind::Dir) {
+                ui.weak(meta.kind.label());
+            }
+        }
+        if node.meta.is_some() || node.is_dir && node.size != 0 {
+            ui.label(format_size_i(node.size, humansize::BINARY));
+        }
+    };
+    if node.children.is_empty() {
+        ui.horizontal(|ui| {
+            ui.add_space(ui.spacing().icon_width + ui.spacing().item_spacing.x);
+            header(ui);
+        });
+        return;
+    }
+    CollapsingState::load_with_default_open(ui.ctx(), id, default_open)
+        .show_header(ui, header)
+        .body(|ui| {
+            for child in &node.children {
+                file_node_ui(ui, child, id, default_open, filt);
+            }
+        });
+}
+
+fn xdg_open(path: &Path) {
+    match Command::new("xdg-open").arg(path).spawn() {
+        // Wait on a thread, so the UI doesn't block while xdg-open runs, and the child is reaped
+        Ok(mut child) => {
+            std::thread::spawn(move || match child.wait() {
+                Ok(status) if !status.success() => eprintln!("xdg-open failed: {status}"),
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to wait for xdg-open: {e}"),
+            });
+        }
+        Err(e) => eprintln!("Failed to run xdg-open: {e}"),
+    }
 }
@@ -0,0 +1,325 @@
+use {
+    super::remote_pkg_list::installed_label_for_remote_pkg,
+    crate::{
+        app::{
+            pac_state::PkgInfo,
+            ui::{
+                PacState,
+                cmd::{Cmd, CmdBuf},
+            },
+        },
+        config::{PkgColumn, PkgColumnConfig, PkgSort, PkgTableConfig},
+    },
+    alpm::PackageReason,
+    chrono::{Local, TimeZone},
+    eframe::egui,
+    egui_extras::{Column, TableBuilder},
+    humansize::format_size_i,
+    smol_str::SmolStr,
+    std::{cmp::Ordering, collections::HashSet},
+};
+
+const ALL_COLUMNS: [PkgColumn; 12] = [
+    PkgColumn::Name,
+    PkgColumn::Version,
+    PkgColumn::Description,
+    PkgColumn::InstalledSize,
+    PkgColumn::DownloadSize,
+    PkgColumn::BuildDate,
+    PkgColumn::InstallDate,
+    PkgColumn::InstallReason,
+    PkgColumn::Repository,
+    PkgColumn::Packager,
+    PkgColumn::Licenses,
+    PkgColumn::Arch,
+];
+
+fn column_label(col: PkgColumn) -> &'static str {
+    match col {
+        PkgColumn::Name => "Name",
+        PkgColumn::Version => "Version",
+        PkgColumn::Description => "Description",
+        PkgColumn::InstalledSize => "Installed size",
+        PkgColumn::DownloadSize => "Download size",
+        PkgColumn::BuildDate => "Build date",
+        PkgColumn::InstallDate => "Install date",
+        PkgColumn::InstallReason => "Install reason",
+        PkgColumn::Repository => "Repository",
+        PkgColumn::Packager => "Packager",
+        PkgColumn::Licenses => "Licenses",
+        PkgColumn::Arch => "Architecture",
+    }
+}
+
+fn default_width(col: PkgColumn) -> f32 {
+    match col {
+        PkgColumn::Name | PkgColumn::Packager => 220.0,
+        PkgColumn::Description => 400.0,
+        PkgColumn::Version | PkgColumn::BuildDate | PkgColumn::InstallDate => 130.0,
+        PkgColumn::Licenses => 150.0,
+        PkgColumn::InstalledSize
+        | PkgColumn::DownloadSize
+        | PkgColumn::InstallReason
+        | PkgColumn::Repository
+        | PkgColumn::Arch => 90.0,
+    }
+}
+
+pub fn format_date(timestamp: i64) -> String {
+    match Local.timestamp_opt(timestamp, 0).single() {
+        Some(date) => date.format("%Y-%m-%d %H:%M").to_string(),
+        None => "<invalid date>".into(),
+    }
+}
+
+/// The installed package corresponding to `pkg`, if any
+fn installed<'a>(pkg: &'a PkgInfo, pac: &'a PacState) -> Option<&'a PkgInfo> {
+    if pkg.is_local() {
+        Some(pkg)
+    } else {
+        pac.find_local(&pkg.name)
+    }
+}
+
+/// The sync db a package is (or would be) installed from
+fn repository<'a>(pkg: &'a PkgInfo, pac: &'a PacState) -> Option<&'a str> {
+    if pkg.is_local() {
+        pac.find_remote(&pkg.name).map(|remote| remote.db.as_str())
+    } else {
+        Some(&pkg.db)
+    }
+}
+
+fn cmp_by(col: PkgColumn, a: &PkgInfo, b: &PkgInfo, pac: &PacState) -> Ordering {
+    match col {
+        PkgColumn::Name => a.name.cmp(&b.name),
+        PkgColumn::Version => alpm::vercmp(a.version.as_str(), b.version.as_str()),
+        PkgColumn::Description => a.desc.cmp(&b.desc),
+        PkgColumn::InstalledSize => a.isize.cmp(&b.isize),
+        PkgColumn::DownloadSize => a.download_size.cmp(&b.download_size),
+        PkgColumn::BuildDate => a.build_date.cmp(&b.build_date),
+        PkgColumn::InstallDate => {
+            let date = |pkg| installed(pkg, pac).and_then(|pkg| pkg.install_date);
+            date(a).cmp(&date(b))
+        }
+        PkgColumn::InstallReason => {
+            let reason = |pkg| installed(pkg, pac).map(|pkg| pkg.reason);
+            reason(a).cmp(&reason(b))
+        }
+        PkgColumn::Repository => repository(a, pac).cmp(&repository(b, pac)),
+        PkgColumn::Packager => a.packager.cmp(&b.packager),
+        PkgColumn::Licenses => a.licenses.cmp(&b.licenses),
+        PkgColumn::Arch => a.arch.cmp(&b.arch),
+    }
+}
+
+/// Sort `indices` (into `list`) according to `sort`
+pub fn sort(pac: &PacState, list: &[PkgInfo], indices: &mut [usize], sort: PkgSort) {
+    indices.sort_by(|&a, &b| {
+        let ord = cmp_by(sort.column, &list[a], &list[b], pac);
+        if sort.descending { ord.reverse() } else { ord }
+    });
+}
+
+fn cell_ui(ui: &mut egui::Ui, col: PkgColumn, pkg: &PkgInfo, pac: &PacState, cmd: &mut CmdBuf) {
+    match col {
+        PkgColumn::Name => {
+            if pkg.is_local() {
+                if ui.link(pkg.name.as_str()).clicked() {
+                    cmd.push(Cmd::OpenPkgTab(pkg.id()));
+                }
+            } else {
+                if ui.link(format!("{}/{}", pkg.db, pkg.name)).clicked() {
+                    cmd.push(Cmd::OpenPkgTab(pkg.id()));
+                }
+                installed_label_for_remote_pkg(ui, cmd, pkg, pac);
+            }
+        }
+        PkgColumn::Version => {
+            ui.label(pkg.version.as_str());
+        }
+        PkgColumn::Description => {
+            ui.label(pkg.desc.as_deref().unwrap_or("<missing description>"));
+        }
+        PkgColumn::InstalledSize => {
+            ui.label(format_size_i(pkg.isize, humansize::BINARY));
+        }
+        PkgColumn::DownloadSize => {
+            // Local packages don't know their package file size
+            if pkg.download_size > 0 {
+                ui.label(format_size_i(pkg.download_size, humansize::BINARY));
+            } else {
+                ui.label("-");
+            }
+        }
+        PkgColumn::BuildDate => {
+            ui.label(format_date(pkg.build_date));
+        }
+        PkgColumn::InstallDate => {
+            match installed(pkg, pac).and_then(|pkg| pkg.install_date) {
+                Some(date) => ui.label(format_date(date)),
+                None => ui.label("-"),
+            };
+        }
+        PkgColumn::InstallReason => {
+            ui.label(match installed(pkg, pac).map(|pkg| pkg.reason) {
+                Some(PackageReason::Explicit) => "explicit",
+                Some(PackageReason::Depend) => "dependency",
+                None => "-",
+            });
+        }
+        PkgColumn::Repository => {
+            ui.label(repository(pkg, pac).unwrap_or("<foreign>"));
+        }
+        PkgColumn::Packager => {
+            ui.label(pkg.packager.as_deref().unwrap_or("-"));
+        }
+        PkgColumn::Licenses => {
+            ui.label(pkg.licenses.join(", "));
+        }
+        PkgColumn::Arch => {
+            ui.label(pkg.arch.as_deref().unwrap_or("-"));
+        }
+    }
+}
+
+/// Show the packages of `list` at `indices`, with a checkbox column if `selected` is given.
+///
+/// Returns whether the sort order was changed.
+pub fn table_ui(
+    ui: &mut egui::Ui,
+    pac: &PacState,
+    list: &[PkgInfo],
+    indices: &[usize],
+    cfg: &mut PkgTableConfig,
+    mut selected: Option<&mut HashSet<SmolStr>>,
+    cmd: &mut CmdBuf,
+) -> bool {
+    let layout: Vec<PkgColumn> = cfg.columns.iter().map(|col| col.kind).collect();
+    // Column widths are remembered per layout, so they don't get mixed up on reordering
+    let mut builder = TableBuilder::new(ui)
+        .id_salt(&layout)
+        .auto_shrink(false)
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center));
+    if selected.is_some() {
+        builder = builder.column(Column::auto());
+    }
+    for (i, col) in cfg.columns.iter().enumerate() {
+        builder = builder.column(if i + 1 == cfg.columns.len() {
+            Column::remainder()
+        } else {
+            Column::initial(col.width.unwrap_or_else(|| default_width(col.kind)))
+                .at_least(30.0)
+                .resizable(true)
+                .clip(true)
+        });
+    }
+    let mut clicked = None;
+    builder
+        .header(18.0, |mut row| {
+            if selected.is_some() {
+                row.col(|_| {});
+            }
+            for col in &mut cfg.columns {
+                let (rect, _) = row.col(|ui| {
+                    let arrow = match cfg.sort {
+                        Some(sort) if sort.column == col.kind && sort.descending => " ⏷",
+                        Some(sort) if sort.column == col.kind => " ⏶",
+                        _ => "",
+                    };
+                    let text = format!("{}{arrow}", column_label(col.kind));
+                    if ui.add(egui::Button::new(text).frame(false)).clicked() {
+                        clicked = Some(col.kind);
+                    }
+                });
+                col.width = Some(rect.width());
+            }
+        })
+        .body(|mut body| {
+            body.ui_mut().style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            body.rows(22.0, indices.len(), |mut row| {
+                let pkg = &list[indices[row.index()]];
+                if let Some(selected) = &mut selected {
+                    row.col(|ui| {
+                        let mut checked = selected.contains(&pkg.name);
+                        if ui.checkbox(&mut checked, "").changed() {
+                            if checked {
+                                selected.insert(pkg.name.clone());
+                            } else {
+                                selected.remove(&pkg.name);
+                            }
+                        }
+                    });
+                }
+                for col in &cfg.columns {
+                    row.col(|ui| cell_ui(ui, col.kind, pkg, pac, cmd));
+                }
+            });
+        });
+    let Some(column) = clicked else {
+        return false;
+    };
+    // Cycle through ascending, descending, and unsorted (relevance order)
+    cfg.sort = match cfg.sort {
+        Some(sort) if sort.column == column && !sort.descending => Some(PkgSort {
+            column,
+            descending: true,
+        }),
+        Some(sort) if sort.column == column => None,
+        _ => Some(PkgSort {
+            column,
+            descending: false,
+        }),
+    };
+    true
+}
+
+/// Menu for choosing and ordering the table columns
+pub fn columns_menu_ui(ui: &mut egui::Ui, cfg: &mut PkgTableConfig) {
+    ui.menu_button("☰ Columns", |ui| {
+        let mut swap = None;
+        let mut remove = None;
+        let n_columns = cfg.columns.len();
+        for (i, col) in cfg.columns.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                    swap = Some((i - 1, i));
+                }
+                if ui
+                    .add_enabled(i + 1 < n_columns, egui::Button::new("⏷"))
+                    .clicked()
+                {
+                    swap = Some((i, i + 1));
+                }
+                // The name column is what opens the package, so it can't be hidden
+                let mut shown = true;
+                if ui
+                    .add_enabled(
+                        col.kind != PkgColumn::Name,
+                        egui::Checkbox::new(&mut shown, column_label(col.kind)),
+                    )
+                    .changed()
+                {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some((a, b)) = swap {
+            cfg.columns.swap(a, b);
+        }
+        if let Some(i) = remove {
+            cfg.columns.remove(i);
+        }
+        ui.separator();
+        for kind in ALL_COLUMNS {
+            if cfg.columns.iter().any(|col| col.kind == kind) {
+                continue;
+            }
+            let mut shown = false;
+            if ui.checkbox(&mut shown, column_label(kind)).changed() {
+                cfg.columns.push(PkgColumnConfig { kind, width: None });
+            }
+        }
+    });
+}
@@ -0,0 +1,241 @@
+use {
+    crate::{
+        alpm_util::{self, PkgId},
+        app::ui::{SharedUiState, cmd::Cmd, trans::format_size_delta},
+        job::Job,
+        trans::{TransOp, TransRequest},
+    },
+    alpm::Alpm,
+    alpm_utils::depends::satisfies_dep,
+    eframe::egui,
+    egui_extras::{Column, TableBuilder},
+    smol_str::SmolStr,
+    std::collections::HashSet,
+};
+
+/// A local package that has a newer version in a sync db, or a sync package that replaces it
+pub struct Update {
+    pub name: SmolStr,
+    /// The sync db package that would replace the local one
+    pub remote: PkgId,
+    pub old_ver: SmolStr,
+    pub new_ver: SmolStr,
+    pub isize_delta: i64,
+    /// The sync package has another name, and replaces the local one
+    pub replacement: bool,
+    /// Held back by `IgnorePkg` or `IgnoreGroup`
+    pub ignored: bool,
+}
+
+#[derive(Default)]
+pub struct UpdatesState {
+    /// Computed in the background, with a libalpm handle of its own
+    updates: Option<Job<anyhow::Result<Vec<Update>>>>,
+    selected: HashSet<SmolStr>,
+}
+
+impl UpdatesState {
+    /// The number of upgrades `pacman -Su` would do, once they are known
+    pub fn count(&self) -> Option<usize> {
+        match self.updates.as_ref()?.result()? {
+            Ok(updates) => Some(updates.iter().filter(|upd| !upd.ignored).count()),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Find the upgrades of a sysupgrade, like libalpm does it: local packages that a sync package
+/// `replaces`, and otherwise local packages for which the first sync db (in pacman.conf order)
+/// that has a package with the same name has a newer version.
+///
+/// Upgrades held back by `IgnorePkg`/`IgnoreGroup` are included, but marked as ignored.
+pub fn find_updates(alpm: &Alpm) -> Vec<Update> {
+    let replacers: Vec<(&alpm::Package, &alpm::Dep)> = alpm
+        .syncdbs()
+        .iter()
+        .flat_map(|db| db.pkgs())
+        .flat_map(|pkg| pkg.replaces().into_iter().map(move |dep| (pkg, dep)))
+        .collect();
+    alpm.localdb()
+        .pkgs()
+        .iter()
+        .filter_map(|local| {
+            let replacer = replacers.iter().find(|(pkg, dep)| {
+                pkg.name() != local.name() && satisfies_dep(dep, local.name(), local.version())
+            });
+            let (remote, replacement) = match replacer {
+                Some(&(pkg, _)) => (pkg, true),
+                None => (local.sync_new_version(alpm.syncdbs())?, false),
+            };
+            Some(Update {
+                name: local.name().into(),
+                remote: PkgId::qualified(remote.db().map_or("", |db| db.name()), remote.name()),
+                old_ver: local.version().as_str().into(),
+                new_ver: remote.version().as_str().into(),
+                isize_delta: remote.isize() - local.isize(),
+                replacement,
+                ignored: remote.should_ignore() || replacement && local.should_ignore(),
+            })
+        })
+        .collect()
+}
+
+fn load_updates() -> anyhow::Result<Vec<Update>> {
+    Ok(find_updates(&alpm_util::alpm_handle()?))
+}
+
+pub fn ui(ui: &mut egui::Ui, ui_state: &mut SharedUiState, tab_state: &mut UpdatesState) {
+    let UpdatesState { updates, selected } = tab_state;
+    let job = updates.get_or_insert_with(|| Job::spawn(load_updates));
+    job.poll();
+    let updates = match job.result() {
+        Some(Ok(updates)) => updates,
+        Some(Err(e)) => {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!("Failed to look for updates: {e:#}"),
+            );
+            return;
+        }
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Looking for updates...");
+            });
+            return;
+        }
+    };
+    let n_selected = updates
+        .iter()
+        .filter(|upd| selected.contains(&upd.name))
+        .count();
+    let n_ignored = updates.iter().filter(|upd| upd.ignored).count();
+    egui::TopBottomPanel::top("updates_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} updates available", updates.len() - n_ignored));
+            if n_ignored != 0 {
+                ui.label(format!("({n_ignored} ignored)"));
+            }
+            ui.separator();
+            if ui.button("🔁 Sync databases").clicked() {
+                ui_state.cmd.push(Cmd::Transact(TransRequest::sync_dbs()));
+            }
+            // Always enabled, as syncing the databases can turn up new updates
+            if ui
+                .button("⬆ Upgrade all")
+                .on_hover_text("Synchronize the databases and upgrade everything (pacman -Syu)")
+                .clicked()
+            {
+                ui_state.cmd.push(Cmd::Transact(TransRequest::sysupgrade()));
+            }
+            if ui
+                .add_enabled(
+                    n_selected != 0,
+                    egui::Button::new(format!("⬆ Upgrade selected ({n_selected})")),
+                )
+                .clicked()
+            {
+                ui_state.cmd.push(Cmd::Transact(TransRequest {
+                    ops: updates
+                        .iter()
+                        .filter(|upd| selected.contains(&upd.name))
+                        .map(|upd| TransOp::Install(upd.remote.clone()))
+                        .collect(),
+                    ..Default::default()
+                }));
+            }
+            ui.separator();
+            if ui.button("Select all").clicked() {
+                selected.extend(
+                    updates
+                        .iter()
+                        .filter(|upd| !upd.ignored)
+                        .map(|upd| upd.name.clone()),
+                );
+            }
+            if ui.button("Select none").clicked() {
+                selected.clear();
+            }
+        });
+        if n_selected != 0 && n_selected != updates.len() - n_ignored {
+            ui.colored_label(
+                egui::Color32::ORANGE,
+                "⚠ Upgrading only some of the packages is a partial upgrade, \
+                 which is unsupported and can break your system",
+            );
+        }
+        ui.add_space(4.0);
+    });
+    TableBuilder::new(ui)
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::remainder())
+        .auto_shrink(false)
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .header(18.0, |mut row| {
+            for label in [
+                "",
+                "Name",
+                "Old version",
+                "New version",
+                "Repository",
+                "Size change",
+            ] {
+                row.col(|ui| {
+                    ui.label(label);
+                });
+            }
+        })
+        .body(|mut body| {
+            body.ui_mut().style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            body.rows(22.0, updates.len(), |mut row| {
+                let upd = &updates[row.index()];
+                row.col(|ui| {
+                    let mut checked = selected.contains(&upd.name);
+                    let checkbox = ui
+                        .add_enabled(!upd.ignored, egui::Checkbox::without_text(&mut checked))
+                        .on_disabled_hover_text("Ignored by IgnorePkg/IgnoreGroup in pacman.conf");
+                    if checkbox.changed() {
+                        if checked {
+                            selected.insert(upd.name.clone());
+                        } else {
+                            selected.remove(&upd.name);
+                        }
+                    }
+                });
+                row.col(|ui| {
+                    if ui.link(upd.name.as_str()).clicked() {
+                        ui_state.cmd.push(Cmd::OpenPkgTab(PkgId::local(&upd.name)));
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(upd.old_ver.as_str());
+                });
+                row.col(|ui| {
+                    if upd.replacement {
+                        ui.label(
+                            egui::RichText::new(format!("{} {}", upd.remote.name, upd.new_ver))
+                                .color(egui::Color32::YELLOW),
+                        )
+                        .on_hover_text(format!("Replaced by {}", upd.remote));
+                    } else {
+                        ui.label(
+                            egui::RichText::new(upd.new_ver.as_str()).color(egui::Color32::YELLOW),
+                        );
+                    }
+                });
+                row.col(|ui| {
+                    if ui.link(upd.remote.db.as_str()).clicked() {
+                        ui_state.cmd.push(Cmd::OpenPkgTab(upd.remote.clone()));
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(format_size_delta(upd.isize_delta));
+                });
+            });
+        });
+}
@@ -0,0 +1,192 @@
+use {
+    crate::{
+        alpm_util::PkgId,
+        app::{
+            ui::{SharedUiState, cmd::Cmd},
+            verify::{IssueKind, VerifyReport, verify_pkgs},
+        },
+        job::Job,
+    },
+    eframe::egui,
+    egui_extras::{Column, TableBuilder},
+    smol_str::SmolStr,
+    std::{
+        collections::HashSet,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    },
+};
+
+/// Check installed packages for missing and modified files, like `pacman -Qk`
+pub struct VerifyState {
+    pub pkgs: Vec<SmolStr>,
+    /// Compare against the mtree too, like `pacman -Qkk`
+    thorough: bool,
+    job: Option<Job<anyhow::Result<VerifyReport>>>,
+    /// Packages checked so far by the running job
+    progress: Arc<AtomicUsize>,
+    shown: HashSet<IssueKind>,
+}
+
+impl VerifyState {
+    pub fn new(pkgs: Vec<SmolStr>) -> Self {
+        let mut this = Self {
+            // Checksumming every file of many packages takes a while
+            thorough: pkgs.len() == 1,
+            pkgs,
+            job: None,
+            progress: Arc::default(),
+            shown: IssueKind::ALL.into_iter().collect(),
+        };
+        this.start();
+        this
+    }
+    fn start(&mut self) {
+        let (pkgs, thorough) = (self.pkgs.clone(), self.thorough);
+        self.progress = Arc::default();
+        let progress = self.progress.clone();
+        self.job = Some(Job::spawn(move || verify_pkgs(&pkgs, thorough, &progress)));
+    }
+}
+
+pub fn ui(ui: &mut egui::Ui, ui_state: &mut SharedUiState, state: &mut VerifyState) {
+    if let Some(job) = &mut state.job {
+        job.poll();
+    }
+    let running = state.job.as_ref().is_some_and(Job::is_pending);
+    egui::TopBottomPanel::top("verify_top_panel").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.thorough, "Thorough (-Qkk)")
+                .on_hover_text(
+                    "Also compare size, permissions, owner, modification time and checksum \
+                     with the package metadata",
+                );
+            if ui
+                .add_enabled(!running, egui::Button::new("⟳ Verify again"))
+                .clicked()
+            {
+                state.start();
+            }
+            ui.separator();
+            if running {
+                ui.spinner();
+                ui.label(format!(
+                    "Checked {}/{} packages...",
+                    state.progress.load(Ordering::Relaxed),
+                    state.pkgs.len()
+                ));
+                ui.ctx().request_repaint();
+            } else if let Some(Ok(report)) = state.job.as_ref().and_then(Job::result) {
+                ui.label(format!(
+                    "Checked {} files of {} packages",
+                    report.n_files, report.n_pkgs
+                ));
+            }
+        });
+        if let Some(Ok(report)) = state.job.as_ref().and_then(Job::result) {
+            ui.horizontal(|ui| {
+                ui.label("Show");
+                for kind in IssueKind::ALL {
+                    let n = report.issues.iter().filter(|i| i.kind() == kind).count();
+                    let mut shown = state.shown.contains(&kind);
+                    if ui
+                        .checkbox(&mut shown, format!("{} ({n})", kind.label()))
+                        .changed()
+                    {
+                        if shown {
+                            state.shown.insert(kind);
+                        } else {
+                            state.shown.remove(&kind);
+                        }
+                    }
+                }
+            });
+            for e in &report.errors {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+        }
+        ui.add_space(4.0);
+    });
+    let report = match state.job.as_ref().and_then(Job::result) {
+        Some(Ok(report)) => report,
+        Some(Err(e)) => {
+            ui.colored_label(egui::Color32::RED, format!("Failed to verify: {e:#}"));
+            return;
+        }
+        None => return,
+    };
+    if report.issues.is_empty() {
+        ui.colored_label(egui::Color32::GREEN, "✔ No problems found");
+        return;
+    }
+    let issues: Vec<_> = report
+        .issues
+        .iter()
+        .filter(|issue| state.shown.contains(&issue.kind()))
+        .collect();
+    TableBuilder::new(ui)
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::auto())
+        .column(Column::remainder())
+        .auto_shrink(false)
+        .striped(true)
+        .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+        .header(18.0, |mut row| {
+            for label in ["Package", "File", "Kind", "Problems"] {
+                row.col(|ui| {
+                    ui.label(label);
+                });
+            }
+        })
+        .body(|mut body| {
+            body.ui_mut().style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            body.rows(22.0, issues.len(), |mut row| {
+                let issue = issues[row.index()];
+                row.col(|ui| {
+                    if ui.link(issue.pkg.as_str()).clicked() {
+                        ui_state.cmd.push(Cmd::OpenPkgTab(PkgId::local(&issue.pkg)));
+                    }
+                });
+                row.col(|ui| {
+                    ui.label(&issue.path).context_menu(|ui| {
+                        if ui.button("Copy path").clicked() {
+                            ui.close_menu();
+                            ui.ctx().copy_text(issue.path.clone());
+                        }
+                    });
+                });
+                row.col(|ui| {
+                    let kind = issue.kind();
+                    let color = match kind {
+                        IssueKind::Missing | IssueKind::Modified => egui::Color32::RED,
+                        IssueKind::Permissions => egui::Color32::YELLOW,
+                        IssueKind::Unreadable => egui::Color32::ORANGE,
+                        IssueKind::Backup => ui.visuals().weak_text_color(),
+                    };
+                    let re = ui.colored_label(color, kind.label());
+                    match kind {
+                        IssueKind::Backup => {
+                            re.on_hover_text(
+                                "Backup files (like configuration) are meant to be edited, \
+                                 so this is usually not corruption",
+                            );
+                        }
+                        IssueKind::Unreadable => {
+                            re.on_hover_text(
+                                "The file couldn't be read to check its contents, \
+                                 e.g. because only root can read it",
+                            );
+                        }
+                        _ => {}
+                    }
+                });
+                row.col(|ui| {
+                    let problems: Vec<_> = issue.problems.iter().map(|p| p.to_string()).collect();
+                    ui.label(problems.join("; "));
+                });
+            });
+        });
+}
@@ -0,0 +1,461 @@
+use {
+    crate::{
+        app::{PacfrontApp, ui::cmd::Cmd},
+        trans::{
+            ProviderChoice, Reason, TransError, TransEvent, TransPlan, TransRequest, spawn_helper,
+            spawn_preview,
+        },
+    },
+    eframe::egui,
+    humansize::format_size_i,
+    smol_str::SmolStr,
+    std::sync::mpsc::{Receiver, TryRecvError},
+};
+
+/// A transaction on its way from preview to completion
+pub enum TransHandler {
+    Preparing {
+        req: TransRequest,
+        recv: Receiver<Result<TransPlan, TransError>>,
+    },
+    Preview {
+        req: TransRequest,
+        plan: Result<TransPlan, TransError>,
+    },
+    Running(TransRun),
+}
+
+impl TransHandler {
+    pub fn new(req: TransRequest) -> Self {
+        if req.needs_preview() {
+            Self::Preparing {
+                recv: spawn_preview(req.clone()),
+                req,
+            }
+        } else {
+            Self::run(&req)
+        }
+    }
+    fn run(req: &TransRequest) -> Self {
+        Self::Running(match spawn_helper(req) {
+            Ok(recv) => TransRun::new(recv),
+            Err(e) => TransRun::spawn_failed(e),
+        })
+    }
+    pub fn is_running(&self) -> bool {
+        matches!(self, Self::Running(run) if !run.is_finished())
+    }
+}
+
+/// Keeps track of a running (or finished) transaction helper
+pub struct TransRun {
+    recv: Option<Receiver<TransEvent>>,
+    /// Package operations, log messages, errors, etc. in the order they arrived
+    log: Vec<TransEvent>,
+    progress: Option<Progress>,
+    download: Option<Download>,
+    failed: bool,
+}
+
+struct Progress {
+    what: SmolStr,
+    pkg: SmolStr,
+    percent: i32,
+    current: usize,
+    total: usize,
+}
+
+struct Download {
+    file: String,
+    downloaded: i64,
+    total: i64,
+}
+
+impl TransRun {
+    pub fn new(recv: Receiver<TransEvent>) -> Self {
+        Self {
+            recv: Some(recv),
+            log: Vec::new(),
+            progress: None,
+            download: None,
+            failed: false,
+        }
+    }
+    pub fn spawn_failed(e: anyhow::Error) -> Self {
+        Self {
+            recv: None,
+            log: vec![TransEvent::Error(TransError::msg(format!("{e:#}")))],
+            progress: None,
+            download: None,
+            failed: true,
+        }
+    }
+    pub fn is_finished(&self) -> bool {
+        self.recv.is_none()
+    }
+    /// Returns whether the helper has just exited
+    fn poll(&mut self) -> bool {
+        let Some(recv) = &self.recv else {
+            return false;
+        };
+        loop {
+            match recv.try_recv() {
+                Ok(ev) => match ev {
+                    TransEvent::Progress {
+                        what,
+                        pkg,
+                        percent,
+                        current,
+                        total,
+                    } => {
+                        self.progress = Some(Progress {
+                            what,
+                            pkg,
+                            percent,
+                            current,
+                            total,
+                        })
+                    }
+                    TransEvent::Download {
+                        file,
+                        downloaded,
+                        total,
+                    } => {
+                        self.download = Some(Download {
+                            file,
+                            downloaded,
+                            total,
+                        })
+                    }
+                    TransEvent::Error(_) => {
+                        self.failed = true;
+                        self.log.push(ev);
+                    }
+                    _ => self.log.push(ev),
+                },
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => {
+                    self.recv = None;
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+enum ModalAction {
+    Apply,
+    Close,
+    /// Pick another provider for a dependency (by name) and preview again
+    ChooseProvider(SmolStr, SmolStr),
+}
+
+pub fn modal_ui(app: &mut PacfrontApp, ctx: &egui::Context) {
+    let Some(handler) = &mut app.ui.shared.trans_handler else {
+        return;
+    };
+    if let TransHandler::Preparing { req, recv } = handler {
+        let plan = match recv.try_recv() {
+            Ok(plan) => Some(plan),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err(TransError::msg("Preview thread exited unexpectedly")))
+            }
+        };
+        if let Some(plan) = plan {
+            *handler = TransHandler::Preview {
+                req: std::mem::take(req),
+                plan,
+            };
+        }
+    }
+    if let TransHandler::Running(run) = handler
+        && run.poll()
+    {
+        // Whether the transaction succeeded or not, the databases might have changed
+        app.ui.shared.cmd.push(Cmd::ReloadPac);
+    }
+    let mut action = None;
+    egui::Modal::new(egui::Id::new("transaction modal")).show(ctx, |ui| {
+        let avail_rect = ui.ctx().available_rect();
+        ui.set_width((avail_rect.width() * 0.5).round());
+        let max_height = (avail_rect.height() * 0.5).round();
+        match handler {
+            TransHandler::Preparing { .. } => {
+                ui.heading("Transaction preview");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Resolving dependencies...");
+                });
+                ui.separator();
+                if ui.button("Cancel").clicked() {
+                    action = Some(ModalAction::Close);
+                }
+            }
+            TransHandler::Preview { req, plan } => {
+                ui.heading("Transaction preview");
+                ui.separator();
+                if req.sync_dbs {
+                    ui.label(
+                        "The databases are synchronized first, \
+                         which can turn up more packages to upgrade than listed here",
+                    );
+                }
+                for (reason, label) in [
+                    (Reason::Explicit, "explicitly installed"),
+                    (Reason::Depend, "installed as dependencies"),
+                ] {
+                    let names: Vec<&str> = req
+                        .set_reasons
+                        .iter()
+                        .filter(|(_, r)| *r == reason)
+                        .map(|(name, _)| name.as_str())
+                        .collect();
+                    if !names.is_empty() {
+                        ui.label(format!("Then marked as {label}: {}", names.join(", ")));
+                    }
+                }
+                if let Ok(plan) = plan
+                    && let Some((dep, provider)) = providers_ui(ui, &plan.providers)
+                {
+                    action = Some(ModalAction::ChooseProvider(dep, provider));
+                }
+                // Even if there's nothing to do yet, there might be after syncing
+                let can_apply = preview_ui(ui, plan, max_height) || req.sync_dbs && plan.is_ok();
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(can_apply, egui::Button::new("✔ Apply"))
+                        .clicked()
+                    {
+                        action = Some(ModalAction::Apply);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        action = Some(ModalAction::Close);
+                    }
+                });
+            }
+            TransHandler::Running(run) => {
+                ui.heading("Transaction");
+                ui.separator();
+                if run_ui(ui, run, max_height) {
+                    action = Some(ModalAction::Close);
+                }
+            }
+        }
+    });
+    match action {
+        Some(ModalAction::Apply) => {
+            if let TransHandler::Preview { req, .. } = handler {
+                *handler = TransHandler::run(req);
+            }
+        }
+        Some(ModalAction::Close) => app.ui.shared.trans_handler = None,
+        Some(ModalAction::ChooseProvider(dep, provider)) => {
+            if let TransHandler::Preview { req, .. } = handler {
+                let mut req = std::mem::take(req);
+                req.providers.retain(|(name, _)| *name != dep);
+                req.providers.push((dep.clone(), provider.clone()));
+                *handler = TransHandler::new(req);
+            }
+            app.ui.shared.provider_choices.insert(dep, provider);
+        }
+        None => {}
+    }
+}
+
+/// Choosers for the dependencies several packages could satisfy, like pacman's provider prompt.
+///
+/// Returns the dependency and package name of a new choice.
+fn providers_ui(ui: &mut egui::Ui, choices: &[ProviderChoice]) -> Option<(SmolStr, SmolStr)> {
+    if choices.is_empty() {
+        return None;
+    }
+    let mut new_choice = None;
+    ui.strong("Several packages provide these dependencies:");
+    egui::Grid::new("trans_providers_grid")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for choice in choices {
+                ui.label(choice.dep.as_str());
+                let mut chosen = choice.chosen;
+                egui::ComboBox::from_id_salt(("provider", &choice.dep))
+                    .selected_text(choice.providers[chosen].to_string())
+                    .show_index(ui, &mut chosen, choice.providers.len(), |i| {
+                        choice.providers[i].to_string()
+                    });
+                if chosen != choice.chosen {
+                    new_choice = Some((choice.dep.clone(), choice.providers[chosen].name.clone()));
+                }
+                ui.end_row();
+            }
+        });
+    ui.separator();
+    new_choice
+}
+
+/// Returns whether there is anything to apply
+fn preview_ui(ui: &mut egui::Ui, plan: &Result<TransPlan, TransError>, max_height: f32) -> bool {
+    let plan = match plan {
+        Ok(plan) => plan,
+        Err(e) => {
+            error_ui(ui, e);
+            return false;
+        }
+    };
+    if plan.items.is_empty() {
+        ui.label("There is nothing to do");
+        return false;
+    }
+    egui::ScrollArea::vertical()
+        .max_height(max_height)
+        .auto_shrink([false, true])
+        .show(ui, |ui| {
+            egui::Grid::new("trans_plan_grid")
+                .striped(true)
+                .num_columns(6)
+                .show(ui, |ui| {
+                    for header in [
+                        "Action",
+                        "Package",
+                        "Old version",
+                        "New version",
+                        "Download",
+                        "Size change",
+                    ] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for item in &plan.items {
+                        ui.label(item.kind.label());
+                        let name = match &item.db {
+                            Some(db) => format!("{db}/{}", item.name),
+                            None => item.name.to_string(),
+                        };
+                        if item.pulled_in {
+                            ui.label(egui::RichText::new(name).italics())
+                                .on_hover_text("Pulled in by dependency resolution");
+                        } else {
+                            ui.label(name);
+                        }
+                        ui.label(item.old_ver.as_deref().unwrap_or("-"));
+                        ui.label(item.new_ver.as_deref().unwrap_or("-"));
+                        if item.download_size > 0 {
+                            ui.label(format_size_i(item.download_size, humansize::BINARY));
+                        } else {
+                            ui.label("-");
+                        }
+                        ui.label(format_size_delta(item.isize_delta));
+                        ui.end_row();
+                    }
+                });
+            if !plan.answers.is_empty() {
+                ui.add_space(8.0);
+                ui.heading(format!("Answered questions ({})", plan.answers.len()));
+                for answer in &plan.answers {
+                    ui.label(answer);
+                }
+            }
+            if !plan.orphans.is_empty() {
+                ui.add_space(8.0);
+                ui.heading(format!("Would-be orphans ({})", plan.orphans.len()));
+                ui.label(
+                    "These were installed as dependencies, and nothing will require them anymore",
+                );
+                ui.horizontal_wrapped(|ui| {
+                    for name in &plan.orphans {
+                        ui.label(name.as_str());
+                    }
+                });
+            }
+        });
+    ui.separator();
+    ui.label(format!(
+        "Total download size: {}",
+        format_size_i(plan.download_size, humansize::BINARY)
+    ));
+    ui.label(format!(
+        "Net installed size change: {}",
+        format_size_delta(plan.isize_delta)
+    ));
+    true
+}
+
+pub fn format_size_delta(delta: i64) -> String {
+    let sign = if delta > 0 { "+" } else { "" };
+    format!("{sign}{}", format_size_i(delta, humansize::BINARY))
+}
+
+fn error_ui(ui: &mut egui::Ui, e: &TransError) {
+    ui.colored_label(egui::Color32::RED, &e.msg);
+    for detail in &e.details {
+        ui.colored_label(egui::Color32::ORANGE, format!(":: {detail}"));
+    }
+}
+
+/// Returns whether the user wants to close the dialog
+fn run_ui(ui: &mut egui::Ui, run: &TransRun, max_height: f32) -> bool {
+    let mut close = false;
+    if let Some(dl) = &run.download {
+        ui.label(format!(
+            "Downloading {} ({}/{})",
+            dl.file,
+            format_size_i(dl.downloaded, humansize::BINARY),
+            format_size_i(dl.total, humansize::BINARY)
+        ));
+        let frac = if dl.total > 0 {
+            dl.downloaded as f32 / dl.total as f32
+        } else {
+            0.0
+        };
+        ui.add(egui::ProgressBar::new(frac).show_percentage());
+    }
+    if let Some(prog) = &run.progress {
+        ui.label(format!(
+            "({}/{}) {} {}",
+            prog.current, prog.total, prog.what, prog.pkg
+        ));
+        ui.add(egui::ProgressBar::new(prog.percent as f32 / 100.0).show_percentage());
+    }
+    egui::ScrollArea::vertical()
+        .max_height(max_height)
+        .auto_shrink([false, true])
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for ev in &run.log {
+                match ev {
+                    TransEvent::Log(line) | TransEvent::Scriptlet(line) => {
+                        ui.label(line);
+                    }
+                    TransEvent::PkgOp(op) => {
+                        ui.label(op.to_string());
+                    }
+                    TransEvent::Hook(desc) => {
+                        ui.label(format!("Running hook: {desc}"));
+                    }
+                    TransEvent::Error(e) => error_ui(ui, e),
+                    TransEvent::Progress { .. } | TransEvent::Download { .. } => {}
+                }
+            }
+        });
+    ui.separator();
+    if run.is_finished() {
+        ui.horizontal(|ui| {
+            if run.failed {
+                ui.colored_label(egui::Color32::RED, "Transaction failed");
+            } else {
+                ui.label("Transaction finished");
+            }
+            if ui.button("Close").clicked() {
+                close = true;
+            }
+        });
+    } else {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label("Running transaction...");
+        });
+    }
+    close
+}
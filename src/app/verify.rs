@@ -0,0 +1,301 @@
+use {
+    super::file_tree::{FileKind, FileMeta, mode_string, mtree_path, read_mtree},
+    crate::alpm_util,
+    humansize::format_size_i,
+    smol_str::SmolStr,
+    std::{
+        collections::{HashMap, HashSet},
+        fmt,
+        fs::Metadata,
+        io::ErrorKind,
+        os::unix::{ffi::OsStrExt, fs::MetadataExt},
+        path::Path,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// The outcome of checking installed packages against their file lists, like `pacman -Qk`
+pub struct VerifyReport {
+    pub n_pkgs: usize,
+    pub n_files: usize,
+    pub issues: Vec<FileIssue>,
+    /// Packages that couldn't be checked (fully)
+    pub errors: Vec<String>,
+}
+
+pub struct FileIssue {
+    pub pkg: SmolStr,
+    pub path: String,
+    /// The file is a backup file (like a config file in `/etc`), so changes are expected
+    pub backup: bool,
+    pub problems: Vec<Problem>,
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum IssueKind {
+    Missing,
+    Modified,
+    Permissions,
+    /// Files that couldn't be checked fully, e.g. because only root can read them
+    Unreadable,
+    /// Backup files that differ from the packaged version
+    Backup,
+}
+
+impl IssueKind {
+    pub const ALL: [Self; 5] = [
+        Self::Missing,
+        Self::Modified,
+        Self::Permissions,
+        Self::Unreadable,
+        Self::Backup,
+    ];
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Missing => "Missing",
+            Self::Modified => "Modified",
+            Self::Permissions => "Permissions",
+            Self::Unreadable => "Unreadable",
+            Self::Backup => "Backup file",
+        }
+    }
+}
+
+pub enum Problem {
+    Missing,
+    Unreadable(String),
+    Type {
+        expected: &'static str,
+        found: &'static str,
+    },
+    Mode {
+        expected: u32,
+        found: u32,
+    },
+    Owner {
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+    Mtime {
+        expected: i64,
+        found: i64,
+    },
+    Size {
+        expected: u64,
+        found: u64,
+    },
+    LinkTarget {
+        expected: String,
+        found: String,
+    },
+    Checksum,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let date = |t: i64| {
+            chrono::DateTime::from_timestamp(t, 0)
+                .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| t.to_string())
+        };
+        match self {
+            Self::Missing => write!(f, "missing"),
+            Self::Unreadable(e) => write!(f, "can't be read: {e}"),
+            Self::Type { expected, found } => write!(f, "type: {expected} → {found}"),
+            Self::Mode { expected, found } => write!(
+                f,
+                "permissions: {} → {}",
+                mode_string(*expected),
+                mode_string(*found)
+            ),
+            Self::Owner { expected, found } => write!(
+                f,
+                "owner: {}:{} → {}:{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            Self::Mtime { expected, found } => {
+                write!(
+                    f,
+                    "modification time: {} → {}",
+                    date(*expected),
+                    date(*found)
+                )
+            }
+            Self::Size { expected, found } => write!(
+                f,
+                "size: {} → {}",
+                format_size_i(*expected, humansize::BINARY),
+                format_size_i(*found, humansize::BINARY)
+            ),
+            Self::LinkTarget { expected, found } => {
+                write!(f, "symlink target: {expected} → {found}")
+            }
+            Self::Checksum => write!(f, "checksum mismatch"),
+        }
+    }
+}
+
+impl FileIssue {
+    pub fn kind(&self) -> IssueKind {
+        if self.backup {
+            IssueKind::Backup
+        } else if self.problems.iter().any(|p| matches!(p, Problem::Missing)) {
+            IssueKind::Missing
+        } else if self
+            .problems
+            .iter()
+            .all(|p| matches!(p, Problem::Mode { .. } | Problem::Owner { .. }))
+        {
+            IssueKind::Permissions
+        } else if self.problems.iter().all(|p| {
+            matches!(
+                p,
+                Problem::Mode { .. } | Problem::Owner { .. } | Problem::Unreadable(_)
+            )
+        }) {
+            IssueKind::Unreadable
+        } else {
+            IssueKind::Modified
+        }
+    }
+}
+
+/// Check that the files of the local packages `names` are on disk. With `thorough`, also
+/// compare them with the package mtree (type, size, permissions, owner, modification time
+/// and checksum), like `pacman -Qkk`.
+///
+/// `progress` counts the packages checked so far.
+pub fn verify_pkgs(
+    names: &[SmolStr],
+    thorough: bool,
+    progress: &AtomicUsize,
+) -> anyhow::Result<VerifyReport> {
+    let alpm = alpm_util::alpm_handle()?;
+    let root = Path::new(alpm.root());
+    let mut report = VerifyReport {
+        n_pkgs: 0,
+        n_files: 0,
+        issues: Vec::new(),
+        errors: Vec::new(),
+    };
+    for name in names {
+        progress.fetch_add(1, Ordering::Relaxed);
+        let pkg = match alpm.localdb().pkg(name.as_str()) {
+            Ok(pkg) => pkg,
+            Err(e) => {
+                report.errors.push(format!("{name}: {e}"));
+                continue;
+            }
+        };
+        report.n_pkgs += 1;
+        let backup: HashSet<&str> = pkg.backup().iter().map(|b| b.name()).collect();
+        let mtree = if thorough {
+            match read_mtree(&mtree_path(&alpm, pkg)) {
+                Ok(mtree) => mtree,
+                Err(e) => {
+                    report
+                        .errors
+                        .push(format!("{name}: Failed to read mtree: {e:#}"));
+                    HashMap::new()
+                }
+            }
+        } else {
+            HashMap::new()
+        };
+        for file in pkg.files().files() {
+            report.n_files += 1;
+            let path = file.name().trim_end_matches('/');
+            let full = root.join(path);
+            let problems = match full.symlink_metadata() {
+                Ok(md) => match mtree.get(path) {
+                    Some(meta) => check_file(&full, &md, meta),
+                    None => Vec::new(),
+                },
+                Err(e) if e.kind() == ErrorKind::NotFound => vec![Problem::Missing],
+                Err(e) => vec![Problem::Unreadable(e.to_string())],
+            };
+            if !problems.is_empty() {
+                report.issues.push(FileIssue {
+                    pkg: name.clone(),
+                    path: format!("/{path}"),
+                    backup: backup.contains(path),
+                    problems,
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Compare a file on disk with its mtree entry
+fn check_file(path: &Path, md: &Metadata, meta: &FileMeta) -> Vec<Problem> {
+    let ft = md.file_type();
+    let found = if ft.is_symlink() {
+        "symlink"
+    } else if ft.is_dir() {
+        "directory"
+    } else if ft.is_file() {
+        "file"
+    } else {
+        "other"
+    };
+    let expected = match meta.kind {
+        FileKind::File => "file",
+        FileKind::Dir => "directory",
+        FileKind::Link(_) => "symlink",
+        FileKind::Other(_) => "other",
+    };
+    if found != expected {
+        return vec![Problem::Type { expected, found }];
+    }
+    let mut problems = Vec::new();
+    // Symlinks always have all permissions
+    if !ft.is_symlink() && md.mode() & 0o7777 != meta.mode {
+        problems.push(Problem::Mode {
+            expected: meta.mode,
+            found: md.mode() & 0o7777,
+        });
+    }
+    if (md.uid(), md.gid()) != (meta.uid, meta.gid) {
+        problems.push(Problem::Owner {
+            expected: (meta.uid, meta.gid),
+            found: (md.uid(), md.gid()),
+        });
+    }
+    // Directories get touched whenever something in them changes
+    if !ft.is_dir() && md.mtime() != meta.mtime {
+        problems.push(Problem::Mtime {
+            expected: meta.mtime,
+            found: md.mtime(),
+        });
+    }
+    match &meta.kind {
+        FileKind::Link(target) => {
+            let found = std::fs::read_link(path)
+                .map(|found| found.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if &found != target {
+                problems.push(Problem::LinkTarget {
+                    expected: target.clone(),
+                    found,
+                });
+            }
+        }
+        FileKind::File => {
+            if md.size() != meta.size {
+                problems.push(Problem::Size {
+                    expected: meta.size,
+                    found: md.size(),
+                });
+            } else if let Some(sha256) = &meta.sha256 {
+                match alpm::compute_sha256sum(path.as_os_str().as_bytes()) {
+                    Ok(sum) if &sum != sha256 => problems.push(Problem::Checksum),
+                    Ok(_) => {}
+                    Err(e) => problems.push(Problem::Unreadable(e.to_string())),
+                }
+            }
+        }
+        _ => {}
+    }
+    problems
+}
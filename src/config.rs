@@ -12,6 +12,58 @@ pub fn cfg_dir() -> Option<PathBuf> {
 #[derive(Serialize, Deserialize, Default)]
 pub struct Config {
     pub color_theme: Option<[Rgb; 12]>,
+    #[serde(default)]
+    pub local_pkg_table: PkgTableConfig,
+    #[serde(default)]
+    pub remote_pkg_table: PkgTableConfig,
+}
+
+/// Layout of a package list table
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PkgTableConfig {
+    pub columns: Vec<PkgColumnConfig>,
+    pub sort: Option<PkgSort>,
+}
+
+impl Default for PkgTableConfig {
+    fn default() -> Self {
+        Self {
+            columns: [PkgColumn::Name, PkgColumn::Version, PkgColumn::Description]
+                .into_iter()
+                .map(|kind| PkgColumnConfig { kind, width: None })
+                .collect(),
+            sort: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PkgColumnConfig {
+    pub kind: PkgColumn,
+    /// `None` until the user has seen (and possibly resized) the column
+    pub width: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Hash)]
+pub enum PkgColumn {
+    Name,
+    Version,
+    Description,
+    InstalledSize,
+    DownloadSize,
+    BuildDate,
+    InstallDate,
+    InstallReason,
+    Repository,
+    Packager,
+    Licenses,
+    Arch,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PkgSort {
+    pub column: PkgColumn,
+    pub descending: bool,
 }
 
 impl Config {
@@ -0,0 +1,79 @@
+use std::{
+    any::Any,
+    panic::AssertUnwindSafe,
+    sync::mpsc::{Receiver, TryRecvError},
+};
+
+/// Some work running on a worker thread, and its result once it's done
+pub struct Job<T> {
+    recv: Option<Receiver<T>>,
+    result: Option<T>,
+}
+
+/// The result of a [`Job`], which stands in for the result if the worker panics
+pub trait JobResult {
+    fn from_panic(msg: String) -> Self;
+}
+
+impl<T> JobResult for anyhow::Result<T> {
+    fn from_panic(msg: String) -> Self {
+        Err(anyhow::anyhow!("Worker thread panicked: {msg}"))
+    }
+}
+
+fn panic_msg(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        (*msg).to_owned()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "<unknown panic>".into()
+    }
+}
+
+impl<T: JobResult + Send + 'static> Job<T> {
+    pub fn spawn(f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (send, recv) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let result = std::panic::catch_unwind(AssertUnwindSafe(f))
+                .unwrap_or_else(|payload| T::from_panic(panic_msg(payload.as_ref())));
+            let _ = send.send(result);
+        });
+        Self {
+            recv: Some(recv),
+            result: None,
+        }
+    }
+}
+
+impl<T> Job<T> {
+    /// Check whether the job has finished. Returns `true` only on the call that picks up the result.
+    pub fn poll(&mut self) -> bool {
+        let Some(recv) = &self.recv else {
+            return false;
+        };
+        match recv.try_recv() {
+            Ok(result) => {
+                self.result = Some(result);
+                self.recv = None;
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => {
+                // Panics are caught and sent as results, so this shouldn't happen. Still report
+                // the job as finished, so callers don't wait for it forever.
+                self.recv = None;
+                true
+            }
+        }
+    }
+    pub fn is_pending(&self) -> bool {
+        self.recv.is_some()
+    }
+    pub fn result(&self) -> Option<&T> {
+        self.result.as_ref()
+    }
+    pub fn take_result(&mut self) -> Option<T> {
+        self.result.take()
+    }
+}
@@ -1,14 +1,19 @@
 #![forbid(unsafe_code)]
-#![feature(let_chains, array_windows)]
+#![feature(let_chains)]
 
 use {app::PacfrontApp, eframe::NativeOptions};
 
 mod alpm_util;
 mod app;
 mod config;
+mod job;
+mod trans;
 
 fn main() -> anyhow::Result<()> {
-    let mut app = PacfrontApp::new()?;
+    if std::env::args().nth(1).as_deref() == Some(trans::HELPER_ARG) {
+        return trans::helper_main();
+    }
+    let mut app = PacfrontApp::new();
     eframe::run_native(
         "pacfront",
         NativeOptions::default(),
@@ -0,0 +1,1028 @@
+//! Package transactions.
+//!
+//! Pacfront itself runs unprivileged. To change the system, it re-executes itself through `pkexec`
+//! with [`HELPER_ARG`], writes a [`TransRequest`] to the helper's stdin, and reads back
+//! [`TransEvent`]s from its stdout, one RON value per line.
+//!
+//! Before that happens, [`preview`] prepares the same transaction unprivileged (without locking the
+//! database), so the user can see what's going to happen.
+
+use {
+    crate::alpm_util::{self, PkgId, pac_file_target, parse_pkg_filename},
+    alpm::{
+        Alpm, AnyDownloadEvent, AnyEvent, AnyQuestion, CommitData, DownloadEvent, Event, LogLevel,
+        Package, PackageOperation, PackageReason, PrepareData, Progress, Question,
+        SelectProviderQuestion, TransFlag,
+    },
+    anyhow::Context as _,
+    serde::{Deserialize, Serialize},
+    smol_str::SmolStr,
+    std::{
+        cell::RefCell,
+        cmp::Ordering,
+        collections::HashSet,
+        fs,
+        io::{BufRead, BufReader, Write},
+        os::unix::fs::{MetadataExt, OpenOptionsExt},
+        path::{Path, PathBuf},
+        process::{Command, Stdio},
+        rc::Rc,
+        sync::mpsc::Receiver,
+    },
+};
+
+/// Command line argument that makes pacfront act as the privileged transaction helper
+pub const HELPER_ARG: &str = "--trans-helper";
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct TransRequest {
+    /// Synchronize the package databases before doing anything else (`pacman -Sy`)
+    pub sync_dbs: bool,
+    /// Synchronize the files databases, which have the file lists of sync packages (`pacman -Fy`)
+    pub sync_files: bool,
+    /// Upgrade every package that has a newer version in the sync dbs (`pacman -Su`)
+    pub sysupgrade: bool,
+    pub ops: Vec<TransOp>,
+    /// Change the install reason of local packages (`pacman -D --asexplicit/--asdeps`),
+    /// after the package changes
+    pub set_reasons: Vec<(SmolStr, Reason)>,
+    /// The package to pick for dependencies that several packages provide, by dependency name.
+    /// Otherwise, the first provider (like the default answer of pacman's prompt) is picked.
+    pub providers: Vec<(SmolStr, SmolStr)>,
+    /// Resolve `.pacnew` and `.pacsave` files, like `pacdiff`
+    pub config_files: Vec<ConfigFileOp>,
+    /// Delete package archives (and their signatures) from the cache directories
+    pub remove_cached: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ConfigFileOp {
+    /// The `.pacnew` or `.pacsave` file
+    pub path: String,
+    pub action: ConfigAction,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum ConfigAction {
+    /// Delete the `.pac*` file, keeping the current one
+    Keep,
+    /// Move the `.pac*` file over the current one
+    Replace,
+    /// Write these contents to the current file, and delete the `.pac*` file
+    Merge(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Reason {
+    Explicit,
+    Depend,
+}
+
+impl From<Reason> for PackageReason {
+    fn from(reason: Reason) -> Self {
+        match reason {
+            Reason::Explicit => Self::Explicit,
+            Reason::Depend => Self::Depend,
+        }
+    }
+}
+
+impl TransRequest {
+    pub fn single(op: TransOp) -> Self {
+        Self {
+            ops: vec![op],
+            ..Default::default()
+        }
+    }
+    pub fn sync_dbs() -> Self {
+        Self {
+            sync_dbs: true,
+            ..Default::default()
+        }
+    }
+    pub fn sync_files() -> Self {
+        Self {
+            sync_files: true,
+            ..Default::default()
+        }
+    }
+    pub fn set_reasons(names: impl IntoIterator<Item = SmolStr>, reason: Reason) -> Self {
+        Self {
+            set_reasons: names.into_iter().map(|name| (name, reason)).collect(),
+            ..Default::default()
+        }
+    }
+    pub fn config_file(path: String, action: ConfigAction) -> Self {
+        Self {
+            config_files: vec![ConfigFileOp { path, action }],
+            ..Default::default()
+        }
+    }
+    /// Synchronize the databases and upgrade everything (`pacman -Syu`)
+    pub fn sysupgrade() -> Self {
+        Self {
+            sync_dbs: true,
+            sysupgrade: true,
+            ..Default::default()
+        }
+    }
+    /// Whether this request changes any packages
+    fn has_pkg_changes(&self) -> bool {
+        self.sysupgrade || !self.ops.is_empty()
+    }
+    /// Whether this request should be shown in the preview dialog before running it
+    pub fn needs_preview(&self) -> bool {
+        self.has_pkg_changes()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum TransOp {
+    /// Install (or upgrade to) a package from a sync db
+    Install(PkgId),
+    /// Reinstall a local package from the first sync db that has it
+    Reinstall(SmolStr),
+    /// Remove a local package
+    Remove(SmolStr),
+    /// Install a package archive, like `pacman -U` (e.g. an older version from the cache)
+    InstallFile(String),
+}
+
+impl TransOp {
+    fn pkg_name(&self) -> &str {
+        match self {
+            TransOp::Install(id) => &id.name,
+            TransOp::Reinstall(name) | TransOp::Remove(name) => name,
+            TransOp::InstallFile(path) => {
+                let file = path.rsplit('/').next().unwrap_or(path);
+                parse_pkg_filename(file).map_or(path, |file| file.name)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum TransEvent {
+    Log(String),
+    Progress {
+        what: SmolStr,
+        pkg: SmolStr,
+        percent: i32,
+        current: usize,
+        total: usize,
+    },
+    Download {
+        file: String,
+        downloaded: i64,
+        total: i64,
+    },
+    PkgOp(PkgOp),
+    Scriptlet(String),
+    Hook(String),
+    Error(TransError),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PkgOp {
+    pub kind: PkgOpKind,
+    pub name: SmolStr,
+    pub old_ver: Option<SmolStr>,
+    pub new_ver: Option<SmolStr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PkgOpKind {
+    Install,
+    Upgrade,
+    Reinstall,
+    Downgrade,
+    Remove,
+}
+
+/// An error, along with the structured details libalpm gave us (missing deps, conflicts, etc.)
+#[derive(Serialize, Deserialize)]
+pub struct TransError {
+    pub msg: String,
+    pub details: Vec<String>,
+}
+
+impl TransError {
+    pub fn msg(msg: impl std::fmt::Display) -> Self {
+        Self {
+            msg: msg.to_string(),
+            details: Vec::new(),
+        }
+    }
+}
+
+impl PkgOpKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            PkgOpKind::Install => "Install",
+            PkgOpKind::Upgrade => "Upgrade",
+            PkgOpKind::Reinstall => "Reinstall",
+            PkgOpKind::Downgrade => "Downgrade",
+            PkgOpKind::Remove => "Remove",
+        }
+    }
+}
+
+impl std::fmt::Display for PkgOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = match self.kind {
+            PkgOpKind::Install => "installing",
+            PkgOpKind::Upgrade => "upgrading",
+            PkgOpKind::Reinstall => "reinstalling",
+            PkgOpKind::Downgrade => "downgrading",
+            PkgOpKind::Remove => "removing",
+        };
+        write!(f, "{verb} {}", self.name)?;
+        match (&self.old_ver, &self.new_ver) {
+            (Some(old), Some(new)) if old != new => write!(f, " ({old} -> {new})"),
+            (Some(ver), _) | (None, Some(ver)) => write!(f, " ({ver})"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl From<PackageOperation<'_>> for PkgOp {
+    fn from(op: PackageOperation) -> Self {
+        let (kind, old, new) = match op {
+            PackageOperation::Install(new) => (PkgOpKind::Install, None, Some(new)),
+            PackageOperation::Upgrade(new, old) => (PkgOpKind::Upgrade, Some(old), Some(new)),
+            PackageOperation::Reinstall(new, old) => (PkgOpKind::Reinstall, Some(old), Some(new)),
+            PackageOperation::Downgrade(new, old) => (PkgOpKind::Downgrade, Some(old), Some(new)),
+            PackageOperation::Remove(old) => (PkgOpKind::Remove, Some(old), None),
+        };
+        Self {
+            kind,
+            name: new
+                .or(old)
+                .map_or_else(SmolStr::default, |pkg| pkg.name().into()),
+            old_ver: old.map(|pkg| pkg.version().as_str().into()),
+            new_ver: new.map(|pkg| pkg.version().as_str().into()),
+        }
+    }
+}
+
+/// What a prepared transaction is going to do
+pub struct TransPlan {
+    /// Dependencies that several packages could satisfy, and which one was picked
+    pub providers: Vec<ProviderChoice>,
+    /// Sorted by operation kind, then name
+    pub items: Vec<PlanItem>,
+    /// Packages installed as dependencies that nothing will require after the transaction
+    pub orphans: Vec<SmolStr>,
+    /// Other questions libalpm asked, and how they were answered (replacements, corrupted
+    /// packages, keys to import)
+    pub answers: Vec<String>,
+    pub download_size: i64,
+    pub isize_delta: i64,
+}
+
+pub struct ProviderChoice {
+    pub dep: SmolStr,
+    pub providers: Vec<PkgId>,
+    pub chosen: usize,
+}
+
+impl ProviderChoice {
+    /// Answer a provider question according to `choices`, and return what was asked
+    fn answer(q: &mut SelectProviderQuestion, choices: &[(SmolStr, SmolStr)]) -> Self {
+        let dep: SmolStr = q.depend().name().into();
+        let providers: Vec<PkgId> = q
+            .providers()
+            .iter()
+            .map(|pkg| PkgId::qualified(pkg.db().map_or("", |db| db.name()), pkg.name()))
+            .collect();
+        let chosen = choices
+            .iter()
+            .find(|(name, _)| *name == dep)
+            .and_then(|(_, choice)| providers.iter().position(|pkg| pkg.name == *choice))
+            .unwrap_or(0);
+        q.set_index(chosen as i32);
+        Self {
+            dep,
+            providers,
+            chosen,
+        }
+    }
+}
+
+pub struct PlanItem {
+    pub kind: PkgOpKind,
+    pub name: SmolStr,
+    /// The sync db the new version comes from
+    pub db: Option<SmolStr>,
+    pub old_ver: Option<SmolStr>,
+    pub new_ver: Option<SmolStr>,
+    pub download_size: i64,
+    pub isize_delta: i64,
+    /// Not requested explicitly, but pulled in by dependency resolution, conflicts, etc.
+    pub pulled_in: bool,
+}
+
+impl TransPlan {
+    fn new(alpm: &Alpm, req: &TransRequest) -> Self {
+        let requested: HashSet<&str> = req.ops.iter().map(TransOp::pkg_name).collect();
+        let localdb = alpm.localdb();
+        let mut items = Vec::new();
+        for new in alpm.trans_add() {
+            let old = localdb.pkg(new.name()).ok();
+            let kind = match old.map(|old| new.version().vercmp(old.version())) {
+                None => PkgOpKind::Install,
+                Some(Ordering::Greater) => PkgOpKind::Upgrade,
+                Some(Ordering::Less) => PkgOpKind::Downgrade,
+                Some(Ordering::Equal) => PkgOpKind::Reinstall,
+            };
+            items.push(PlanItem {
+                kind,
+                name: new.name().into(),
+                db: new.db().map(|db| db.name().into()),
+                old_ver: old.map(|old| old.version().as_str().into()),
+                new_ver: Some(new.version().as_str().into()),
+                download_size: new.download_size(),
+                isize_delta: new.isize() - old.map_or(0, |old| old.isize()),
+                pulled_in: !(requested.contains(new.name())
+                    || req.sysupgrade && kind == PkgOpKind::Upgrade),
+            });
+        }
+        for old in alpm.trans_remove() {
+            items.push(PlanItem {
+                kind: PkgOpKind::Remove,
+                name: old.name().into(),
+                db: None,
+                old_ver: Some(old.version().as_str().into()),
+                new_ver: None,
+                download_size: 0,
+                isize_delta: -old.isize(),
+                pulled_in: !requested.contains(old.name()),
+            });
+        }
+        items.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+        Self {
+            providers: Vec::new(),
+            orphans: would_be_orphans(alpm),
+            answers: Vec::new(),
+            download_size: items.iter().map(|item| item.download_size).sum(),
+            isize_delta: items.iter().map(|item| item.isize_delta).sum(),
+            items,
+        }
+    }
+}
+
+/// Find the local dependency packages that are required right now, but nothing will require
+/// after the prepared transaction is committed.
+fn would_be_orphans(alpm: &Alpm) -> Vec<SmolStr> {
+    let added: Vec<&Package> = alpm.trans_add().iter().collect();
+    let touched: HashSet<&str> = added
+        .iter()
+        .copied()
+        .chain(alpm.trans_remove())
+        .map(|pkg| pkg.name())
+        .collect();
+    let untouched: Vec<&Package> = alpm
+        .localdb()
+        .pkgs()
+        .into_iter()
+        .filter(|pkg| !touched.contains(pkg.name()))
+        .collect();
+    // What the set of installed packages looks like after the transaction
+    let after: Vec<&Package> = untouched.iter().chain(&added).copied().collect();
+    let mut orphans = Vec::new();
+    for pkg in untouched {
+        if pkg.reason() != PackageReason::Depend {
+            continue;
+        }
+        let required_by = pkg.required_by();
+        // Only the packages whose requirers are touched by the transaction can become orphans
+        if required_by.is_empty() || !required_by.iter().any(|req| touched.contains(req)) {
+            continue;
+        }
+        let still_required = after.iter().any(|other| {
+            other.name() != pkg.name()
+                && other.depends().iter().any(|dep| {
+                    alpm_utils::depends::satisfies(
+                        dep,
+                        pkg.name(),
+                        pkg.version(),
+                        pkg.provides().iter(),
+                    )
+                })
+        });
+        if !still_required {
+            orphans.push(pkg.name().into());
+        }
+    }
+    orphans
+}
+
+/// Prepare the transaction for `req` without committing it, and report what it would do
+pub fn preview(req: &TransRequest) -> Result<TransPlan, TransError> {
+    let mut alpm = alpm_util::alpm_handle().map_err(TransError::msg)?;
+    // Record the questions, so the user can see the answers (and pick other providers)
+    let asked = Rc::new(RefCell::new(Answers::default()));
+    let choices = req.providers.clone();
+    alpm.set_question_cb(Rc::clone(&asked), move |question, asked| {
+        answer_question(question, &choices, &mut asked.borrow_mut());
+    });
+    alpm.trans_init(TransFlag::NO_LOCK)
+        .map_err(TransError::msg)?;
+    let result = add_targets(&alpm, req)
+        .and_then(|()| prepare(&mut alpm))
+        .map(|()| {
+            let asked = asked.take();
+            TransPlan {
+                providers: asked.providers,
+                answers: asked.notes,
+                ..TransPlan::new(&alpm, req)
+            }
+        });
+    alpm.trans_release().map_err(TransError::msg)?;
+    result
+}
+
+/// Run [`preview`] on a worker thread
+pub fn spawn_preview(req: TransRequest) -> Receiver<Result<TransPlan, TransError>> {
+    let (send, recv) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = send.send(preview(&req));
+    });
+    recv
+}
+
+/// Spawn the privileged helper for `req`, and return a receiver for the events it reports.
+///
+/// The channel disconnects once the helper has exited.
+pub fn spawn_helper(req: &TransRequest) -> anyhow::Result<Receiver<TransEvent>> {
+    let exe = std::env::current_exe().context("Couldn't determine pacfront executable path")?;
+    let mut child = Command::new("pkexec")
+        .arg(exe)
+        .arg(HELPER_ARG)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to spawn pkexec")?;
+    let mut stdin = child.stdin.take().context("Missing helper stdin")?;
+    stdin.write_all(ron::to_string(req)?.as_bytes())?;
+    drop(stdin);
+    let reader = BufReader::new(child.stdout.take().context("Missing helper stdout")?);
+    let err_reader = BufReader::new(child.stderr.take().context("Missing helper stderr")?);
+    let (send, recv) = std::sync::mpsc::channel();
+    let send2 = send.clone();
+    std::thread::spawn(move || {
+        for line in reader.lines() {
+            let ev = match line {
+                Ok(line) => ron::from_str(&line).unwrap_or(TransEvent::Log(line)),
+                Err(e) => TransEvent::Error(TransError::msg(e)),
+            };
+            if send.send(ev).is_err() {
+                return;
+            }
+        }
+        match child.wait() {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                let _ = send.send(TransEvent::Error(TransError::msg(format!(
+                    "Helper exited ({status})"
+                ))));
+            }
+            Err(e) => {
+                let _ = send.send(TransEvent::Error(TransError::msg(e)));
+            }
+        }
+    });
+    std::thread::spawn(move || {
+        for line in err_reader.lines().map_while(Result::ok) {
+            if send2.send(TransEvent::Log(line)).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(recv)
+}
+
+fn set_reasons(alpm: &Alpm, reasons: &[(SmolStr, Reason)]) -> Result<(), TransError> {
+    for (name, reason) in reasons {
+        let pkg = alpm
+            .localdb()
+            .pkg(name.as_str())
+            .map_err(|e| TransError::msg(format!("{name}: {e}")))?;
+        pkg.set_reason((*reason).into())
+            .map_err(|e| TransError::msg(format!("Failed to set install reason of {name}: {e}")))?;
+        let reason = match reason {
+            Reason::Explicit => "explicitly installed",
+            Reason::Depend => "installed as dependency",
+        };
+        emit(&TransEvent::Log(format!(
+            "{name}: install reason set to {reason}"
+        )));
+    }
+    Ok(())
+}
+
+fn remove_cached(paths: &[String]) -> Result<(), TransError> {
+    let conf = alpm_utils::config::Config::new().map_err(TransError::msg)?;
+    for path in paths {
+        let file = Path::new(path);
+        let in_cache = file
+            .parent()
+            .is_some_and(|dir| conf.cache_dir.iter().any(|cache| Path::new(cache) == dir));
+        let is_pkg = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_pkg_filename)
+            .is_some();
+        if !(in_cache && is_pkg) {
+            return Err(TransError::msg(format!(
+                "Not a package archive in a cache directory: {path}"
+            )));
+        }
+        fs::remove_file(file).map_err(|e| TransError::msg(format!("{path}: {e}")))?;
+        // Not every package has a detached signature
+        let _ = fs::remove_file(format!("{path}.sig"));
+        emit(&TransEvent::Log(format!("Removed {path}")));
+    }
+    Ok(())
+}
+
+fn resolve_config_files(ops: &[ConfigFileOp], root: &str) -> Result<(), TransError> {
+    if ops.is_empty() {
+        return Ok(());
+    }
+    let etc = Path::new(root)
+        .join("etc")
+        .canonicalize()
+        .map_err(|e| TransError::msg(format!("{root}etc: {e}")))?;
+    for op in ops {
+        let (path, target) = config_file_paths(&op.path, &etc)
+            .map_err(|e| TransError::msg(format!("{}: {e}", op.path)))?;
+        let (shown, shown_target) = (path.display(), target.display());
+        let result = match &op.action {
+            ConfigAction::Keep => fs::remove_file(&path).map(|()| format!("Removed {shown}")),
+            ConfigAction::Replace => {
+                fs::rename(&path, &target).map(|()| format!("Moved {shown} to {shown_target}"))
+            }
+            ConfigAction::Merge(contents) => write_merged(&path, &target, contents)
+                .map(|()| format!("Merged {shown} into {shown_target}")),
+        };
+        match result {
+            Ok(msg) => emit(&TransEvent::Log(msg)),
+            Err(e) => return Err(TransError::msg(format!("{shown}: {e}"))),
+        }
+    }
+    Ok(())
+}
+
+/// Check that `path` is a `.pacnew` or `.pacsave` file under `etc` (which must be canonical),
+/// and return it and the file it's a version of, with the directory resolved.
+///
+/// Neither may be a symlink, so the changes can't be redirected out of `etc`.
+fn config_file_paths(path: &str, etc: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let path = Path::new(path);
+    let name = path.file_name().and_then(|name| name.to_str());
+    let (Some(dir), Some((target_name, _))) = (path.parent(), name.and_then(pac_file_target))
+    else {
+        return Err("Not a .pacnew or .pacsave file".into());
+    };
+    if !path.is_absolute() {
+        return Err("Not an absolute path".into());
+    }
+    let dir = dir.canonicalize().map_err(|e| e.to_string())?;
+    if !dir.starts_with(etc) {
+        return Err(format!("Not in {}", etc.display()));
+    }
+    let target = dir.join(target_name);
+    let path = dir.join(name.unwrap_or_default());
+    if !fs::symlink_metadata(&path)
+        .map_err(|e| e.to_string())?
+        .is_file()
+    {
+        return Err("Not a regular file".into());
+    }
+    if fs::symlink_metadata(&target).is_ok_and(|md| md.file_type().is_symlink()) {
+        return Err(format!("{} is a symlink", target.display()));
+    }
+    Ok((path, target))
+}
+
+/// Replace `target` with the merged `contents` (through a temporary file, so it's never half
+/// written), keeping its owner and permissions, then delete the `.pac*` file at `path`
+fn write_merged(path: &Path, target: &Path, contents: &str) -> std::io::Result<()> {
+    // A new file gets the owner and permissions of the packaged one
+    let md = match fs::symlink_metadata(target) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => fs::symlink_metadata(path)?,
+        md => md?,
+    };
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(target.file_name().unwrap_or_default());
+    tmp_name.push(".pacfront-merge");
+    let tmp = target.with_file_name(tmp_name);
+    // Left over by an earlier failure
+    if let Err(e) = fs::remove_file(&tmp)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        return Err(e);
+    }
+    let write = || {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        std::os::unix::fs::fchown(&file, Some(md.uid()), Some(md.gid()))?;
+        file.set_permissions(md.permissions())?;
+        file.sync_all()?;
+        fs::rename(&tmp, target)
+    };
+    if let Err(e) = write() {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    fs::remove_file(path)
+}
+
+/// Entry point of the privileged helper process
+pub fn helper_main() -> anyhow::Result<()> {
+    let req: TransRequest = ron::de::from_reader(std::io::stdin().lock())?;
+    if let Err(e) = run(&req) {
+        emit(&TransEvent::Error(e));
+    }
+    Ok(())
+}
+
+fn emit(ev: &TransEvent) {
+    match ron::to_string(ev) {
+        Ok(line) => println!("{line}"),
+        Err(e) => eprintln!("Failed to serialize event: {e}"),
+    }
+}
+
+fn run(req: &TransRequest) -> Result<(), TransError> {
+    let mut alpm = alpm_util::alpm_handle().map_err(TransError::msg)?;
+    set_callbacks(&alpm, req);
+    if req.sync_dbs {
+        emit(&TransEvent::Log(
+            "Synchronizing package databases...".into(),
+        ));
+        alpm.syncdbs_mut()
+            .update(false)
+            .map_err(|e| TransError::msg(format!("Failed to synchronize databases: {e}")))?;
+    }
+    if req.sync_files {
+        emit(&TransEvent::Log("Synchronizing files databases...".into()));
+        let mut files_alpm = alpm_util::alpm_files_handle().map_err(TransError::msg)?;
+        set_callbacks(&files_alpm, req);
+        files_alpm
+            .syncdbs_mut()
+            .update(false)
+            .map_err(|e| TransError::msg(format!("Failed to synchronize files databases: {e}")))?;
+    }
+    resolve_config_files(&req.config_files, alpm.root())?;
+    remove_cached(&req.remove_cached)?;
+    if req.has_pkg_changes() {
+        alpm.trans_init(TransFlag::NONE).map_err(TransError::msg)?;
+        let result = add_targets(&alpm, req)
+            .and_then(|()| prepare(&mut alpm))
+            .and_then(|()| commit(&mut alpm));
+        alpm.trans_release().map_err(TransError::msg)?;
+        result?;
+    }
+    // After the transaction, so that the reasons of the packages it installed can be set too
+    if !req.set_reasons.is_empty() {
+        // Like pacman, hold the database lock while changing the reasons
+        alpm.trans_init(TransFlag::NONE).map_err(TransError::msg)?;
+        let result = set_reasons(&alpm, &req.set_reasons);
+        alpm.trans_release().map_err(TransError::msg)?;
+        result?;
+    }
+    Ok(())
+}
+
+fn add_targets(alpm: &Alpm, req: &TransRequest) -> Result<(), TransError> {
+    if req.sysupgrade {
+        alpm.sync_sysupgrade(false).map_err(TransError::msg)?;
+    }
+    for op in &req.ops {
+        match op {
+            TransOp::Install(id) => {
+                let pkg = alpm
+                    .syncdbs()
+                    .iter()
+                    .find(|db| db.name() == id.db)
+                    .and_then(|db| db.pkg(id.name.as_str()).ok())
+                    .ok_or_else(|| TransError::msg(format!("Package not found: {id}")))?;
+                alpm.trans_add_pkg(pkg)
+                    .map_err(|e| TransError::msg(format!("{id}: {e}")))?;
+            }
+            TransOp::Reinstall(name) => {
+                let pkg = alpm
+                    .syncdbs()
+                    .iter()
+                    .find_map(|db| db.pkg(name.as_str()).ok())
+                    .ok_or_else(|| {
+                        TransError::msg(format!("{name} is not available in any sync db"))
+                    })?;
+                alpm.trans_add_pkg(pkg)
+                    .map_err(|e| TransError::msg(format!("{name}: {e}")))?;
+            }
+            TransOp::Remove(name) => {
+                let pkg = alpm
+                    .localdb()
+                    .pkg(name.as_str())
+                    .map_err(|e| TransError::msg(format!("{name}: {e}")))?;
+                alpm.trans_remove_pkg(pkg)
+                    .map_err(|e| TransError::msg(format!("{name}: {e}")))?;
+            }
+            TransOp::InstallFile(path) => {
+                let pkg = alpm
+                    .pkg_load(path.as_str(), true, alpm.local_file_siglevel())
+                    .map_err(|e| TransError::msg(format!("{path}: {e}")))?;
+                alpm.trans_add_pkg(pkg)
+                    .map_err(|e| TransError::msg(format!("{path}: {e}")))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn prepare(alpm: &mut Alpm) -> Result<(), TransError> {
+    if let Err(e) = alpm.trans_prepare() {
+        let details = match e.data() {
+            PrepareData::PkgInvalidArch(pkgs) => pkgs
+                .iter()
+                .map(|pkg| format!("{} has an invalid architecture", pkg.name()))
+                .collect(),
+            PrepareData::UnsatisfiedDeps(missing) => missing
+                .iter()
+                .map(|miss| match miss.causing_pkg() {
+                    Some(causing) => format!(
+                        "removing {causing} breaks dependency '{}' required by {}",
+                        miss.depend(),
+                        miss.target()
+                    ),
+                    None => format!(
+                        "unable to satisfy dependency '{}' required by {}",
+                        miss.depend(),
+                        miss.target()
+                    ),
+                })
+                .collect(),
+            PrepareData::ConflictingDeps(conflicts) => conflicts
+                .iter()
+                .map(|conflict| {
+                    format!(
+                        "{} and {} are in conflict ({})",
+                        conflict.package1().name(),
+                        conflict.package2().name(),
+                        conflict.reason()
+                    )
+                })
+                .collect(),
+        };
+        return Err(TransError {
+            msg: format!("Failed to prepare transaction: {e}"),
+            details,
+        });
+    }
+    Ok(())
+}
+
+fn commit(alpm: &mut Alpm) -> Result<(), TransError> {
+    if alpm.trans_add().is_empty() && alpm.trans_remove().is_empty() {
+        emit(&TransEvent::Log("There is nothing to do".into()));
+        return Ok(());
+    }
+    if let Err(e) = alpm.trans_commit() {
+        let details = match e.data() {
+            CommitData::FileConflict(conflicts) => conflicts
+                .iter()
+                .map(|conflict| match conflict.conflicting_target() {
+                    Some(other) => format!(
+                        "{}: /{} exists in both '{}' and '{other}'",
+                        conflict.target(),
+                        conflict.file(),
+                        conflict.target()
+                    ),
+                    None => format!(
+                        "{}: /{} exists in filesystem",
+                        conflict.target(),
+                        conflict.file()
+                    ),
+                })
+                .collect(),
+            CommitData::PkgInvalid(files) => files
+                .iter()
+                .map(|file| format!("{file} is invalid or corrupted"))
+                .collect(),
+        };
+        return Err(TransError {
+            msg: format!("Failed to commit transaction: {e}"),
+            details,
+        });
+    }
+    Ok(())
+}
+
+fn set_callbacks(alpm: &Alpm, req: &TransRequest) {
+    alpm.set_log_cb((), |level, msg, _| {
+        if level.intersects(LogLevel::ERROR | LogLevel::WARNING) {
+            emit(&TransEvent::Log(msg.trim_end().to_owned()));
+        }
+    });
+    alpm.set_event_cb((), |ev: AnyEvent, _| {
+        let ev = match ev.event() {
+            Event::PackageOperationStart(op) => TransEvent::PkgOp(op.operation().into()),
+            Event::ScriptletInfo(info) => TransEvent::Scriptlet(info.line().trim_end().to_owned()),
+            Event::HookRunStart(hook) => {
+                TransEvent::Hook(hook.desc().unwrap_or(hook.name()).to_owned())
+            }
+            Event::PacnewCreated(ev) => {
+                TransEvent::Log(format!("{} installed as {0}.pacnew", ev.file()))
+            }
+            Event::PacsaveCreated(ev) => {
+                TransEvent::Log(format!("{} saved as {0}.pacsave", ev.file()))
+            }
+            Event::DatabaseMissing(ev) => TransEvent::Log(format!(
+                "database file for '{}' does not exist",
+                ev.dbname()
+            )),
+            _ => return,
+        };
+        emit(&ev);
+    });
+    alpm.set_progress_cb((), |progress, pkg, percent, total, current, _| {
+        let what = match progress {
+            Progress::AddStart => "installing",
+            Progress::UpgradeStart => "upgrading",
+            Progress::DowngradeStart => "downgrading",
+            Progress::ReinstallStart => "reinstalling",
+            Progress::RemoveStart => "removing",
+            Progress::ConflictsStart => "checking for file conflicts",
+            Progress::DiskspaceStart => "checking available disk space",
+            Progress::IntegrityStart => "checking package integrity",
+            Progress::LoadStart => "loading package files",
+            Progress::KeyringStart => "checking keys in keyring",
+        };
+        emit(&TransEvent::Progress {
+            what: what.into(),
+            pkg: pkg.into(),
+            percent,
+            current,
+            total,
+        });
+    });
+    alpm.set_dl_cb((), |file, ev: AnyDownloadEvent, _| {
+        let (downloaded, total) = match ev.event() {
+            DownloadEvent::Progress(progress) => (progress.downloaded, progress.total),
+            DownloadEvent::Completed(completed) => (completed.total, completed.total),
+            _ => return,
+        };
+        emit(&TransEvent::Download {
+            file: file.to_owned(),
+            downloaded,
+            total,
+        });
+    });
+    alpm.set_question_cb(
+        (req.providers.clone(), Answers::default()),
+        |question, (choices, answers)| answer_question(question, choices, answers),
+    );
+}
+
+/// The questions libalpm asked during a transaction
+#[derive(Default)]
+struct Answers {
+    providers: Vec<ProviderChoice>,
+    /// The other questions that were answered, described
+    notes: Vec<String>,
+}
+
+/// Answer a question the way `pacman --noconfirm` would, except for the providers the user
+/// picked.
+///
+/// Both [`preview`] and the helper answer through this, so the preview shows what will run.
+fn answer_question(question: AnyQuestion, choices: &[(SmolStr, SmolStr)], answers: &mut Answers) {
+    match question.question() {
+        Question::Replace(q) => {
+            q.set_replace(true);
+            answers.notes.push(format!(
+                "{} replaces {} ({})",
+                q.newpkg().name(),
+                q.oldpkg().name(),
+                q.newdb().name()
+            ));
+        }
+        Question::Corrupted(mut q) => {
+            q.set_remove(true);
+            answers.notes.push(format!(
+                "{} is corrupted ({}), and will be deleted",
+                q.filepath(),
+                q.reason()
+            ));
+        }
+        Question::ImportKey(mut q) => {
+            q.set_import(true);
+            answers.notes.push(format!(
+                "The key {} ({}) will be imported",
+                q.fingerprint(),
+                q.uid()
+            ));
+        }
+        Question::SelectProvider(mut q) => {
+            answers
+                .providers
+                .push(ProviderChoice::answer(&mut q, choices));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::os::unix::fs::{PermissionsExt, symlink},
+    };
+
+    /// A fresh directory with an `etc` directory and an `outside` directory in it
+    fn scratch_root(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("pacfront-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("etc/app")).unwrap();
+        fs::create_dir_all(root.join("outside")).unwrap();
+        let etc = root.join("etc").canonicalize().unwrap();
+        (root, etc)
+    }
+
+    fn check(path: &Path, etc: &Path) -> Result<(PathBuf, PathBuf), String> {
+        config_file_paths(path.to_str().unwrap(), etc)
+    }
+
+    #[test]
+    fn config_file_paths_accepts_pac_files_in_etc() {
+        let (root, etc) = scratch_root("accept");
+        fs::write(etc.join("app/app.conf.pacnew"), "new").unwrap();
+        fs::write(etc.join("app/app.conf.pacsave.1"), "old").unwrap();
+        assert_eq!(
+            check(&etc.join("app/app.conf.pacnew"), &etc),
+            Ok((etc.join("app/app.conf.pacnew"), etc.join("app/app.conf")))
+        );
+        assert_eq!(
+            check(&etc.join("app/../app/app.conf.pacsave.1"), &etc),
+            Ok((etc.join("app/app.conf.pacsave.1"), etc.join("app/app.conf")))
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn config_file_paths_rejects_escapes() {
+        let (root, etc) = scratch_root("reject");
+        let outside = root.join("outside");
+        fs::write(outside.join("x.pacnew"), "").unwrap();
+        fs::write(etc.join("app/a.conf"), "").unwrap();
+        // Not a .pac* file
+        assert!(check(&etc.join("app/a.conf"), &etc).is_err());
+        // Relative, or with `..` leading out of etc
+        assert!(config_file_paths("etc/app/x.pacnew", &etc).is_err());
+        assert!(check(&etc.join("../outside/x.pacnew"), &etc).is_err());
+        // A directory symlink leading out of etc
+        symlink(&outside, etc.join("link")).unwrap();
+        assert!(check(&etc.join("link/x.pacnew"), &etc).is_err());
+        // The .pac* file is a symlink
+        symlink(outside.join("x.pacnew"), etc.join("app/b.conf.pacnew")).unwrap();
+        assert!(check(&etc.join("app/b.conf.pacnew"), &etc).is_err());
+        // The target is a symlink
+        fs::write(etc.join("app/c.conf.pacnew"), "").unwrap();
+        symlink(outside.join("c.conf"), etc.join("app/c.conf")).unwrap();
+        assert!(check(&etc.join("app/c.conf.pacnew"), &etc).is_err());
+        // Missing
+        assert!(check(&etc.join("app/d.conf.pacnew"), &etc).is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn write_merged_keeps_permissions() {
+        let (root, etc) = scratch_root("merge");
+        let (path, target) = (etc.join("app/a.conf.pacnew"), etc.join("app/a.conf"));
+        fs::write(&path, "new\n").unwrap();
+        fs::write(&target, "old\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).unwrap();
+        write_merged(&path, &target, "merged\n").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "merged\n");
+        assert_eq!(fs::metadata(&target).unwrap().mode() & 0o777, 0o640);
+        assert!(!path.exists());
+        let leftovers = fs::read_dir(etc.join("app")).unwrap().count();
+        assert_eq!(leftovers, 1);
+        // Without a current file, the packaged file's permissions are used
+        let (path, target) = (etc.join("app/b.conf.pacnew"), etc.join("app/b.conf"));
+        fs::write(&path, "new\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        write_merged(&path, &target, "merged\n").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "merged\n");
+        assert_eq!(fs::metadata(&target).unwrap().mode() & 0o777, 0o600);
+        fs::remove_dir_all(root).unwrap();
+    }
+}